@@ -0,0 +1,490 @@
+//! `#[derive(PuzzleParse)]`: generate a `FromStr` impl and a structured
+//! error type from a pattern describing a line of puzzle input, e.g.
+//!
+//! ```text
+//! #[derive(PuzzleParse)]
+//! #[pattern("{x1},{y1} -> {x2},{y2}")]
+//! pub struct Line { x1: i16, y1: i16, x2: i16, y2: i16 }
+//!
+//! #[derive(PuzzleParse)]
+//! pub enum Command {
+//!     #[pattern("forward {0}")]
+//!     Forward(u64),
+//!     #[pattern("up {0}")]
+//!     Up(u64),
+//! }
+//! ```
+//!
+//! Each `{field}` names a field (or tuple index) and is parsed with the
+//! field's own `FromStr`; it runs up to the literal text that follows it.
+//! A space in the literal text matches any run of whitespace, and `{{` and
+//! `}}` match literal braces. An enum picks the first variant whose leading
+//! literal (its keyword) matches, so no variant's keyword may start with an
+//! earlier variant's.
+//!
+//! For a type `Foo`, the generated `FooParseError` has a `Syntax` variant for
+//! input that doesn't fit the pattern, and one variant per field holding the
+//! field's own parse error. It implements `Spanned`, so failures point at the
+//! offending part of the line. The generated code relies on
+//! `crate::utils::pattern` and `crate::diagnostic`.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::{
+    parse_macro_input, Attribute, Data, DeriveInput, Error, Fields, Ident, LitStr, Result, Type,
+};
+
+#[proc_macro_derive(PuzzleParse, attributes(pattern))]
+pub fn derive_puzzle_parse(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(&input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// A piece of a pattern: text to match exactly, or a field to parse.
+#[derive(Debug, PartialEq)]
+enum Segment {
+    Literal(String),
+    Field(String),
+}
+
+/// Split a pattern into segments, collapsing runs of spaces in its literal
+/// text.
+fn parse_pattern(pattern: &str) -> std::result::Result<Vec<Segment>, String> {
+    let mut segments = Vec::new();
+    let mut literal = String::new();
+    let mut chars = pattern.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                literal.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                literal.push('}');
+            }
+            '{' => {
+                let mut name = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => name.push(c),
+                        None => return Err(format!("unclosed `{{{}`", name)),
+                    }
+                }
+                let name = name.trim().to_owned();
+                if name.is_empty() {
+                    return Err("empty field name `{}`".to_owned());
+                }
+                if literal.is_empty() && matches!(segments.last(), Some(Segment::Field(_))) {
+                    return Err(format!(
+                        "`{{{}}}` must be separated from the previous field by literal text",
+                        name
+                    ));
+                }
+                if !literal.is_empty() {
+                    segments.push(Segment::Literal(std::mem::take(&mut literal)));
+                }
+                segments.push(Segment::Field(name));
+            }
+            '}' => return Err("unmatched `}`; use `}}` for a literal brace".to_owned()),
+            ' ' if literal.ends_with(' ') => {}
+            c => literal.push(c),
+        }
+    }
+
+    if !literal.is_empty() {
+        segments.push(Segment::Literal(literal));
+    }
+    Ok(segments)
+}
+
+/// A field of the deriving type, as seen by the generated parser.
+struct FieldInfo {
+    /// How the pattern refers to the field: its name or tuple index.
+    key: String,
+    ty: Type,
+    /// Local variable the parsed value is bound to.
+    binding: Ident,
+    /// Variant of the error type for failures to parse the field.
+    variant: Ident,
+}
+
+/// One `#[pattern]` and the fields it fills in.
+struct Pattern {
+    text: String,
+    segments: Vec<Segment>,
+    fields: Vec<FieldInfo>,
+}
+
+fn expand(input: &DeriveInput) -> Result<TokenStream2> {
+    if !input.generics.params.is_empty() {
+        return Err(Error::new_spanned(
+            &input.generics,
+            "PuzzleParse does not support generic types",
+        ));
+    }
+
+    let name = &input.ident;
+    let error = format_ident!("{}ParseError", name);
+
+    let (body, patterns) = match &input.data {
+        Data::Struct(data) => {
+            let pattern = pattern(&input.attrs, input, &data.fields, None)?;
+            let parse = parse_steps(&pattern.segments, &pattern.fields, &error);
+            let build = construct(quote!(#name), &data.fields, &pattern.fields);
+            let body = quote! {
+                let __rest = __line;
+                #parse
+                ::std::result::Result::Ok(#build)
+            };
+            (body, vec![pattern])
+        }
+        Data::Enum(data) => {
+            let mut arms = Vec::new();
+            let mut keywords = Vec::new();
+            let mut patterns = Vec::new();
+            let mut seen: Vec<(&Ident, String)> = Vec::new();
+            for variant in &data.variants {
+                let ident = &variant.ident;
+                let pattern = pattern(&variant.attrs, variant, &variant.fields, Some(ident))?;
+                let keyword = match pattern.segments.first() {
+                    Some(Segment::Literal(keyword)) => keyword.clone(),
+                    _ => {
+                        return Err(Error::new_spanned(
+                            variant,
+                            "enum variant patterns must start with a keyword",
+                        ))
+                    }
+                };
+                // Variants are tried in order and the first keyword to match
+                // decides, so no keyword may start with an earlier one.
+                if let Some((earlier, _)) = seen
+                    .iter()
+                    .find(|(_, prefix)| keyword.starts_with(prefix.as_str()))
+                {
+                    return Err(Error::new_spanned(
+                        variant,
+                        format!(
+                            "variant {} can never match: its pattern starts with {}'s keyword",
+                            ident, earlier
+                        ),
+                    ));
+                }
+                seen.push((ident, keyword.clone()));
+                let parse = parse_steps(&pattern.segments[1..], &pattern.fields, &error);
+                let build = construct(quote!(#name::#ident), &variant.fields, &pattern.fields);
+                arms.push(quote! {
+                    if let ::std::result::Result::Ok(__rest) =
+                        crate::utils::pattern::expect(__line, __line, #keyword)
+                    {
+                        #parse
+                        return ::std::result::Result::Ok(#build);
+                    }
+                });
+                keywords.push(keyword.trim().to_owned());
+                patterns.push(pattern);
+            }
+            let body = quote! {
+                #(#arms)*
+                ::std::result::Result::Err(#error::Syntax(
+                    crate::utils::pattern::keyword_error(__line, &[#(#keywords),*]),
+                ))
+            };
+            (body, patterns)
+        }
+        Data::Union(_) => {
+            return Err(Error::new_spanned(
+                input,
+                "PuzzleParse can only be derived for structs and enums",
+            ))
+        }
+    };
+
+    let error_type = error_type(name, &error, &input.vis, &patterns);
+    Ok(quote! {
+        impl ::std::str::FromStr for #name {
+            type Err = #error;
+
+            fn from_str(__line: &str) -> ::std::result::Result<Self, Self::Err> {
+                #body
+            }
+        }
+
+        #error_type
+    })
+}
+
+/// Read the `#[pattern]` attribute of a struct or variant and check that it
+/// mentions each of its fields exactly once. `variant` prefixes the error
+/// variants of an enum's fields.
+fn pattern(
+    attrs: &[Attribute],
+    item: &dyn quote::ToTokens,
+    fields: &Fields,
+    variant: Option<&Ident>,
+) -> Result<Pattern> {
+    let attr = attrs
+        .iter()
+        .find(|attr| attr.path().is_ident("pattern"))
+        .ok_or_else(|| Error::new_spanned(item, "missing #[pattern(\"...\")] attribute"))?;
+    let lit: LitStr = attr.parse_args()?;
+    let text = lit.value();
+    let segments = parse_pattern(&text).map_err(|e| Error::new_spanned(&lit, e))?;
+
+    let single = fields.len() == 1;
+    let infos: Vec<FieldInfo> = fields
+        .iter()
+        .enumerate()
+        .map(|(i, field)| {
+            let key = match &field.ident {
+                Some(ident) => ident.to_string(),
+                None => i.to_string(),
+            };
+            let variant = match (variant, &field.ident) {
+                (Some(v), _) if single => v.clone(),
+                (Some(v), Some(ident)) => format_ident!("{}{}", v, camel_case(&ident.to_string())),
+                (Some(v), None) => format_ident!("{}{}", v, i),
+                (None, Some(ident)) => format_ident!("{}", camel_case(&ident.to_string())),
+                (None, None) => format_ident!("Field{}", i),
+            };
+            FieldInfo {
+                binding: format_ident!("__{}", key),
+                key,
+                ty: field.ty.clone(),
+                variant,
+            }
+        })
+        .collect();
+
+    for info in &infos {
+        let uses = segments
+            .iter()
+            .filter(|s| **s == Segment::Field(info.key.clone()))
+            .count();
+        if uses != 1 {
+            let problem = if uses == 0 {
+                "is missing from"
+            } else {
+                "appears more than once in"
+            };
+            return Err(Error::new_spanned(
+                &lit,
+                format!("field `{}` {} the pattern", info.key, problem),
+            ));
+        }
+    }
+    for segment in &segments {
+        if let Segment::Field(key) = segment {
+            if !infos.iter().any(|info| &info.key == key) {
+                return Err(Error::new_spanned(
+                    &lit,
+                    format!("no field named `{}`", key),
+                ));
+            }
+        }
+    }
+
+    Ok(Pattern {
+        text,
+        segments,
+        fields: infos,
+    })
+}
+
+fn camel_case(snake: &str) -> String {
+    snake
+        .split('_')
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
+
+/// Statements matching `segments` against `__rest`, binding each field and
+/// then checking that the whole line was used.
+fn parse_steps(segments: &[Segment], fields: &[FieldInfo], error: &Ident) -> TokenStream2 {
+    let steps = segments
+        .iter()
+        .enumerate()
+        .map(|(i, segment)| match segment {
+            Segment::Literal(literal) => quote! {
+                let __rest = crate::utils::pattern::expect(__line, __rest, #literal)
+                    .map_err(#error::Syntax)?;
+            },
+            Segment::Field(key) => {
+                let info = fields.iter().find(|info| &info.key == key).unwrap();
+                let (binding, ty, variant) = (&info.binding, &info.ty, &info.variant);
+                let next = match segments.get(i + 1) {
+                    Some(Segment::Literal(next)) => quote!(::std::option::Option::Some(#next)),
+                    _ => quote!(::std::option::Option::None),
+                };
+                quote! {
+                    let (__token, __rest) = crate::utils::pattern::field(__rest, #next);
+                    let #binding = __token.parse::<#ty>().map_err(|source| #error::#variant {
+                        span: crate::diagnostic::span_of(__line, __token),
+                        source,
+                    })?;
+                }
+            }
+        });
+
+    quote! {
+        #(#steps)*
+        crate::utils::pattern::end(__line, __rest).map_err(#error::Syntax)?;
+    }
+}
+
+/// An expression building the struct or variant `path` from parsed fields.
+fn construct(path: TokenStream2, fields: &Fields, infos: &[FieldInfo]) -> TokenStream2 {
+    let bindings = infos.iter().map(|info| &info.binding);
+    match fields {
+        Fields::Named(named) => {
+            let names = named.named.iter().map(|field| &field.ident);
+            quote!(#path { #(#names: #bindings),* })
+        }
+        Fields::Unnamed(_) => quote!(#path(#(#bindings),*)),
+        Fields::Unit => path,
+    }
+}
+
+fn error_type(
+    name: &Ident,
+    error: &Ident,
+    vis: &syn::Visibility,
+    patterns: &[Pattern],
+) -> TokenStream2 {
+    let fields: Vec<(&Pattern, &FieldInfo)> = patterns
+        .iter()
+        .flat_map(|pattern| pattern.fields.iter().map(move |info| (pattern, info)))
+        .collect();
+    let variants: Vec<&Ident> = fields.iter().map(|(_, info)| &info.variant).collect();
+    let types = fields.iter().map(|(_, info)| &info.ty);
+    let messages = fields
+        .iter()
+        .map(|(pattern, info)| format!("Invalid {{{}}} in {:?}", info.key, pattern.text));
+    let doc = format!("Why a line failed to parse as a [`{}`].", name);
+
+    quote! {
+        #[doc = #doc]
+        #[derive(Debug)]
+        #vis enum #error {
+            /// The line doesn't match the pattern.
+            Syntax(crate::utils::combinator::SyntaxError),
+            #(#variants {
+                span: ::std::ops::Range<usize>,
+                source: <#types as ::std::str::FromStr>::Err,
+            },)*
+        }
+
+        impl ::std::fmt::Display for #error {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                match self {
+                    #error::Syntax(e) => ::std::fmt::Display::fmt(e, f),
+                    #(#error::#variants { .. } => f.write_str(#messages),)*
+                }
+            }
+        }
+
+        impl ::std::error::Error for #error {
+            fn source(&self) -> ::std::option::Option<&(dyn ::std::error::Error + 'static)> {
+                match self {
+                    #error::Syntax(_) => ::std::option::Option::None,
+                    #(#error::#variants { source, .. } => ::std::option::Option::Some(source),)*
+                }
+            }
+        }
+
+        impl crate::diagnostic::Spanned for #error {
+            fn span(&self) -> ::std::option::Option<::std::ops::Range<usize>> {
+                match self {
+                    #error::Syntax(e) => crate::diagnostic::Spanned::span(e),
+                    #(#error::#variants { span, .. } => ::std::option::Option::Some(span.clone()),)*
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{expand, parse_pattern, Segment};
+    use syn::parse_quote;
+
+    #[test]
+    fn test_parse_pattern() {
+        let lit = |s: &str| Segment::Literal(s.to_owned());
+        let field = |s: &str| Segment::Field(s.to_owned());
+
+        assert_eq!(
+            parse_pattern("{x1},{y1} -> {x2},{y2}"),
+            Ok(vec![
+                field("x1"),
+                lit(","),
+                field("y1"),
+                lit(" -> "),
+                field("x2"),
+                lit(","),
+                field("y2"),
+            ])
+        );
+        assert_eq!(
+            parse_pattern("forward   {0}"),
+            Ok(vec![lit("forward "), field("0")])
+        );
+        assert_eq!(
+            parse_pattern("{{{ 0 }}}"),
+            Ok(vec![lit("{"), field("0"), lit("}")])
+        );
+
+        assert!(parse_pattern("{a}{b}").is_err());
+        assert!(parse_pattern("{a").is_err());
+        assert!(parse_pattern("a}").is_err());
+        assert!(parse_pattern("{}").is_err());
+    }
+
+    #[test]
+    fn test_shadowed_keywords() {
+        let err = expand(&parse_quote! {
+            enum Command {
+                #[pattern("up {0}")]
+                Up(u8),
+                #[pattern("up {0} {1}")]
+                UpBy(u8, u8),
+            }
+        })
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "variant UpBy can never match: its pattern starts with Up's keyword"
+        );
+
+        // "add" would match the start of "addx 1" and then fail.
+        assert!(expand(&parse_quote! {
+            enum Op {
+                #[pattern("add")]
+                Add,
+                #[pattern("addx {0}")]
+                AddX(i32),
+            }
+        })
+        .is_err());
+        assert!(expand(&parse_quote! {
+            enum Op {
+                #[pattern("addx {0}")]
+                AddX(i32),
+                #[pattern("add")]
+                Add,
+            }
+        })
+        .is_ok());
+    }
+}
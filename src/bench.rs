@@ -0,0 +1,431 @@
+use std::time::Duration;
+
+use anyhow::anyhow;
+use serde::{Deserialize, Serialize};
+use structopt::StructOpt;
+
+use crate::history::{self, Entry};
+use crate::input::InputSource;
+use crate::report::{self, csv_opt, format_duration, Align, Format, Record};
+use crate::solver::{Day, Parts};
+use crate::utils::{timed, ProblemResult};
+
+#[derive(StructOpt, Debug)]
+pub struct Bench {
+    /// Day to benchmark. Benchmarks every day if omitted.
+    #[structopt(long = "day")]
+    day: Option<u32>,
+
+    /// Timed iterations of each phase.
+    #[structopt(short = "n", long = "iterations", default_value = "100")]
+    iterations: usize,
+
+    /// Untimed iterations of each phase before timing starts.
+    #[structopt(long = "warmup", default_value = "10")]
+    warmup: usize,
+
+    /// Append the results to the benchmark history file.
+    #[structopt(long = "save")]
+    save: bool,
+
+    /// Compare medians against the most recent saved results for the same
+    /// input, failing if any phase regressed.
+    #[structopt(long = "compare")]
+    compare: bool,
+
+    /// Only compare against saved results from this git revision.
+    #[structopt(long = "baseline", requires = "compare")]
+    baseline: Option<String>,
+
+    /// Percentage slowdown of the median beyond which a phase has regressed.
+    #[structopt(long = "threshold", default_value = "10")]
+    threshold: f64,
+
+    /// Benchmark history file.
+    #[structopt(long = "history", default_value = "bench-history.jsonl")]
+    history: String,
+
+    /// Arguments passed on to each day's solver, e.g. `-- -i input.txt`.
+    #[structopt(last = true)]
+    args: Vec<String>,
+}
+
+/// A separately timed step of solving a day.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Phase {
+    Parse,
+    Part1,
+    Part2,
+}
+
+impl Phase {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Phase::Parse => "parse",
+            Phase::Part1 => "part1",
+            Phase::Part2 => "part2",
+        }
+    }
+}
+
+/// Summary statistics over repeated timings of a phase.
+#[derive(Debug, Clone, Copy)]
+pub struct Stats {
+    pub iterations: usize,
+    pub min: Duration,
+    pub median: Duration,
+    pub mean: Duration,
+    pub stddev: Duration,
+}
+
+impl Stats {
+    pub fn from_samples(samples: &mut [Duration]) -> Stats {
+        assert!(!samples.is_empty(), "no samples to summarize");
+        samples.sort();
+
+        let n = samples.len();
+        let median = if n % 2 == 1 {
+            samples[n / 2]
+        } else {
+            (samples[n / 2 - 1] + samples[n / 2]) / 2
+        };
+
+        let secs: Vec<f64> = samples.iter().map(Duration::as_secs_f64).collect();
+        let mean = secs.iter().sum::<f64>() / n as f64;
+        let variance = if n > 1 {
+            secs.iter().map(|s| (s - mean).powi(2)).sum::<f64>() / (n - 1) as f64
+        } else {
+            0.0
+        };
+
+        Stats {
+            iterations: n,
+            min: samples[0],
+            median,
+            mean: Duration::from_secs_f64(mean),
+            stddev: Duration::from_secs_f64(variance.sqrt()),
+        }
+    }
+}
+
+/// Run `f` `warmup` times, then time `iterations` further runs.
+pub fn measure<T>(
+    warmup: usize,
+    iterations: usize,
+    mut f: impl FnMut() -> ProblemResult<T>,
+) -> ProblemResult<Stats> {
+    for _ in 0..warmup {
+        std::hint::black_box(f()?);
+    }
+
+    let mut samples = Vec::with_capacity(iterations);
+    for _ in 0..iterations.max(1) {
+        let (result, time) = timed(&mut f);
+        std::hint::black_box(result?);
+        samples.push(time);
+    }
+
+    Ok(Stats::from_samples(&mut samples))
+}
+
+#[derive(Serialize, Debug)]
+struct BenchRecord {
+    day: u32,
+    phase: Phase,
+    iterations: usize,
+    min_ns: u64,
+    median_ns: u64,
+    mean_ns: u64,
+    stddev_ns: u64,
+}
+
+impl BenchRecord {
+    fn new(day: u32, phase: Phase, stats: &Stats) -> BenchRecord {
+        BenchRecord {
+            day,
+            phase,
+            iterations: stats.iterations,
+            min_ns: stats.min.as_nanos() as u64,
+            median_ns: stats.median.as_nanos() as u64,
+            mean_ns: stats.mean.as_nanos() as u64,
+            stddev_ns: stats.stddev.as_nanos() as u64,
+        }
+    }
+}
+
+impl Record for BenchRecord {
+    const CSV_HEADER: &'static str = "day,phase,iterations,min_ns,median_ns,mean_ns,stddev_ns";
+
+    fn csv_fields(&self) -> Vec<String> {
+        vec![
+            self.day.to_string(),
+            self.phase.as_str().to_owned(),
+            self.iterations.to_string(),
+            self.min_ns.to_string(),
+            self.median_ns.to_string(),
+            self.mean_ns.to_string(),
+            self.stddev_ns.to_string(),
+        ]
+    }
+}
+
+impl Bench {
+    pub fn run(&self, days: &[Day], parts: Parts, format: Format) -> ProblemResult {
+        let args: Vec<&str> = self.args.iter().map(String::as_str).collect();
+
+        let revision = history::git_revision();
+        let timestamp = history::now();
+
+        let mut results = Vec::new();
+        let mut entries = Vec::new();
+        let mut stdin = None;
+        for day in days.iter().filter(|d| self.day.is_none_or(|n| n == d.day)) {
+            let mut puzzle = match day.with_args(&args) {
+                Ok(puzzle) => puzzle,
+                // Arguments meant for other days needn't stop the rest.
+                Err(e) if self.day.is_none() => {
+                    let message = e.to_string();
+                    let reason = message.lines().next().unwrap_or_default();
+                    let reason = reason.trim_start_matches("error: ");
+                    eprintln!("warning: skipping day {}: {}\n", day.day, reason);
+                    continue;
+                }
+                Err(e) => return Err(e),
+            };
+            // Stdin can only be read once, so read it up front and give every
+            // parse the same text.
+            if let InputSource::Stdin = puzzle.source() {
+                if stdin.is_none() {
+                    stdin = Some(InputSource::Stdin.read_to_string()?);
+                }
+                let text = stdin.clone().unwrap_or_default();
+                puzzle = day.with_source(&args, InputSource::Text(text))?;
+            }
+            let input_hash = history::input_hash(&puzzle.source());
+
+            for (phase, stats) in puzzle.bench(parts, self.warmup, self.iterations)? {
+                results.push((day.day, phase, stats));
+                entries.push(Entry {
+                    timestamp,
+                    revision: revision.clone(),
+                    input_hash: input_hash.clone(),
+                    day: day.day,
+                    phase,
+                    iterations: stats.iterations,
+                    median_ns: stats.median.as_nanos() as u64,
+                    mean_ns: stats.mean.as_nanos() as u64,
+                });
+            }
+        }
+
+        let mut regressions = 0;
+        if self.compare {
+            let saved = history::load(&self.history)?;
+            let comparisons: Vec<Comparison> = entries
+                .iter()
+                .map(|entry| {
+                    let baseline = history::baseline(&saved, entry, self.baseline.as_deref());
+                    Comparison::new(entry, baseline, self.threshold)
+                })
+                .collect();
+            regressions = comparisons.iter().filter(|c| c.regressed).count();
+
+            if format == Format::Text {
+                print_comparisons(&comparisons);
+            } else {
+                report::print_records(format, &comparisons)?;
+            }
+        } else if format == Format::Text {
+            print_stats(&results);
+        } else {
+            let records: Vec<BenchRecord> = results
+                .iter()
+                .map(|(day, phase, stats)| BenchRecord::new(*day, *phase, stats))
+                .collect();
+            report::print_records(format, &records)?;
+        }
+
+        if self.save {
+            history::append(&self.history, &entries)?;
+        }
+
+        if regressions > 0 {
+            return Err(anyhow!(
+                "{} phase(s) regressed by more than {}%",
+                regressions,
+                self.threshold
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// A phase's median compared with its saved baseline.
+#[derive(Serialize, Debug)]
+struct Comparison {
+    day: u32,
+    phase: Phase,
+    baseline_revision: Option<String>,
+    baseline_median_ns: Option<u64>,
+    median_ns: u64,
+    change_pct: Option<f64>,
+    regressed: bool,
+}
+
+impl Comparison {
+    fn new(current: &Entry, baseline: Option<&Entry>, threshold: f64) -> Comparison {
+        let change_pct = baseline.map(|b| {
+            let base = b.median_ns.max(1) as f64;
+            (current.median_ns as f64 - base) / base * 100.0
+        });
+
+        Comparison {
+            day: current.day,
+            phase: current.phase,
+            baseline_revision: baseline.map(|b| b.revision.clone()),
+            baseline_median_ns: baseline.map(|b| b.median_ns),
+            median_ns: current.median_ns,
+            change_pct,
+            regressed: change_pct.is_some_and(|c| c > threshold),
+        }
+    }
+}
+
+impl Record for Comparison {
+    const CSV_HEADER: &'static str =
+        "day,phase,baseline_revision,baseline_median_ns,median_ns,change_pct,regressed";
+
+    fn csv_fields(&self) -> Vec<String> {
+        vec![
+            self.day.to_string(),
+            self.phase.as_str().to_owned(),
+            csv_opt(&self.baseline_revision),
+            csv_opt(&self.baseline_median_ns),
+            self.median_ns.to_string(),
+            csv_opt(&self.change_pct.map(|c| format!("{:.2}", c))),
+            self.regressed.to_string(),
+        ]
+    }
+}
+
+fn print_comparisons(comparisons: &[Comparison]) {
+    let rows: Vec<Vec<String>> = comparisons
+        .iter()
+        .map(|c| {
+            let ns = |n: u64| format_duration(Duration::from_nanos(n));
+            vec![
+                c.day.to_string(),
+                c.phase.as_str().to_owned(),
+                c.baseline_revision.clone().unwrap_or_default(),
+                c.baseline_median_ns.map(ns).unwrap_or_default(),
+                ns(c.median_ns),
+                c.change_pct
+                    .map(|p| format!("{:+.1}%", p))
+                    .unwrap_or_default(),
+                match (c.regressed, c.baseline_median_ns) {
+                    (true, _) => "REGRESSED",
+                    (false, Some(_)) => "ok",
+                    (false, None) => "no baseline",
+                }
+                .to_owned(),
+            ]
+        })
+        .collect();
+
+    report::print_aligned(
+        &[
+            "Day", "Phase", "Baseline", "Before", "After", "Change", "Status",
+        ],
+        &[
+            Align::Right,
+            Align::Left,
+            Align::Left,
+            Align::Right,
+            Align::Right,
+            Align::Right,
+            Align::Left,
+        ],
+        &rows,
+    );
+}
+
+fn print_stats(results: &[(u32, Phase, Stats)]) {
+    let rows: Vec<Vec<String>> = results
+        .iter()
+        .map(|(day, phase, stats)| {
+            vec![
+                day.to_string(),
+                phase.as_str().to_owned(),
+                format_duration(stats.min),
+                format_duration(stats.median),
+                format_duration(stats.mean),
+                format_duration(stats.stddev),
+            ]
+        })
+        .collect();
+
+    report::print_aligned(
+        &["Day", "Phase", "Min", "Median", "Mean", "Stddev"],
+        &[
+            Align::Right,
+            Align::Left,
+            Align::Right,
+            Align::Right,
+            Align::Right,
+            Align::Right,
+        ],
+        &rows,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Comparison, Phase, Stats};
+    use crate::history::Entry;
+    use std::time::Duration;
+
+    #[test]
+    fn test_stats() {
+        let mut samples: Vec<Duration> = [4, 1, 3, 2]
+            .iter()
+            .map(|&ms| Duration::from_millis(ms))
+            .collect();
+        let stats = Stats::from_samples(&mut samples);
+
+        assert_eq!(stats.min, Duration::from_millis(1));
+        assert_eq!(stats.median, Duration::from_micros(2500));
+        assert_eq!(stats.mean, Duration::from_micros(2500));
+        assert_eq!(stats.stddev.as_micros(), 1290);
+    }
+
+    #[test]
+    fn test_comparison() {
+        let entry = |revision: &str, median_ns| Entry {
+            timestamp: 0,
+            revision: revision.to_owned(),
+            input_hash: None,
+            day: 1,
+            phase: Phase::Parse,
+            iterations: 10,
+            median_ns,
+            mean_ns: median_ns,
+        };
+        let base = entry("aaa", 1000);
+
+        let slower = Comparison::new(&entry("bbb", 1150), Some(&base), 10.0);
+        assert_eq!(slower.change_pct, Some(15.0));
+        assert!(slower.regressed);
+        assert_eq!(slower.baseline_revision.as_deref(), Some("aaa"));
+
+        let within = Comparison::new(&entry("bbb", 1050), Some(&base), 10.0);
+        assert_eq!(within.change_pct, Some(5.0));
+        assert!(!within.regressed);
+        assert!(!Comparison::new(&entry("bbb", 500), Some(&base), 10.0).regressed);
+
+        let unbased = Comparison::new(&entry("bbb", 1_000_000), None, 10.0);
+        assert_eq!(unbased.change_pct, None);
+        assert!(!unbased.regressed);
+    }
+}
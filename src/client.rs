@@ -0,0 +1,274 @@
+use serde::Deserialize;
+use structopt::StructOpt;
+use thiserror::Error;
+
+pub const DEFAULT_BASE_URL: &str = "https://adventofcode.com";
+pub const DEFAULT_YEAR: u32 = 2021;
+
+/// Settings read from the config file, e.g.
+///
+/// session = "53616c7465645f5f..."
+/// base_url = "http://localhost:8000"
+/// year = 2021
+///
+/// Every setting is optional.
+#[derive(Deserialize, Debug, Default)]
+pub struct Config {
+    session: Option<String>,
+    base_url: Option<String>,
+    year: Option<u32>,
+}
+
+/// Options for talking to the puzzle server, shared by commands that use it.
+#[derive(StructOpt, Debug)]
+pub struct ClientOpt {
+    /// Base URL of the puzzle server. Overrides $AOC_BASE_URL and the config
+    /// file.
+    #[structopt(long = "base-url")]
+    base_url: Option<String>,
+
+    /// Config file providing `session`, `base_url` and `year`. The session
+    /// token can also be given in $AOC_SESSION.
+    #[structopt(long = "config", default_value = "aoc.toml")]
+    config: String,
+}
+
+#[derive(Error, Debug)]
+pub enum ClientError {
+    #[error("Failed to read config file: {path}")]
+    ConfigRead {
+        path: String,
+        source: std::io::Error,
+    },
+
+    #[error("Failed to parse config file: {path}")]
+    ConfigParse {
+        path: String,
+        source: toml::de::Error,
+    },
+
+    #[error("No session token: set $AOC_SESSION or `session` in {config}")]
+    NoSession { config: String },
+
+    #[error("Request to {url} failed with status {status}: {body}")]
+    Status {
+        url: String,
+        status: u16,
+        body: String,
+    },
+
+    #[error("Request to {url} failed")]
+    Transport {
+        url: String,
+        source: Box<ureq::Error>,
+    },
+
+    #[error("Failed to read response from {url}")]
+    Body { url: String, source: std::io::Error },
+}
+
+impl ClientOpt {
+    /// Load the config file. A missing file is an empty config.
+    fn config(&self) -> Result<Config, ClientError> {
+        let text = match std::fs::read_to_string(&self.config) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Config::default()),
+            Err(source) => {
+                return Err(ClientError::ConfigRead {
+                    path: self.config.clone(),
+                    source,
+                })
+            }
+        };
+
+        toml::from_str(&text).map_err(|source| ClientError::ConfigParse {
+            path: self.config.clone(),
+            source,
+        })
+    }
+
+    /// Build a client, taking each setting from the command line, then the
+    /// environment, then the config file.
+    pub fn client(&self) -> Result<Client, ClientError> {
+        let config = self.config()?;
+        let env = |name: &str| std::env::var(name).ok().filter(|v| !v.is_empty());
+
+        let session =
+            env("AOC_SESSION")
+                .or(config.session)
+                .ok_or_else(|| ClientError::NoSession {
+                    config: self.config.clone(),
+                })?;
+        let base_url = self
+            .base_url
+            .clone()
+            .or_else(|| env("AOC_BASE_URL"))
+            .or(config.base_url)
+            .unwrap_or_else(|| DEFAULT_BASE_URL.to_owned());
+
+        Ok(Client {
+            base_url: base_url.trim_end_matches('/').to_owned(),
+            year: config.year.unwrap_or(DEFAULT_YEAR),
+            session,
+            agent: ureq::agent(),
+        })
+    }
+}
+
+/// An authenticated client for the puzzle server.
+pub struct Client {
+    base_url: String,
+    year: u32,
+    session: String,
+    agent: ureq::Agent,
+}
+
+impl Client {
+    /// URL of a path under the current year, e.g. `day/1/input`.
+    fn url(&self, path: &str) -> String {
+        format!("{}/{}/{}", self.base_url, self.year, path)
+    }
+
+    pub fn year(&self) -> u32 {
+        self.year
+    }
+
+    /// Download a day's puzzle input.
+    pub fn input(&self, day: u32) -> Result<String, ClientError> {
+        let url = self.url(&format!("day/{}/input", day));
+        let response = self.agent.get(&url).set("Cookie", &self.cookie()).call();
+        read_response(&url, response)
+    }
+
+    /// Submit an answer, returning the body of the server's reply.
+    pub fn submit(&self, day: u32, part: u8, answer: &str) -> Result<String, ClientError> {
+        let url = self.url(&format!("day/{}/answer", day));
+        let response = self
+            .agent
+            .post(&url)
+            .set("Cookie", &self.cookie())
+            .send_form(&[("level", &part.to_string()), ("answer", answer)]);
+        read_response(&url, response)
+    }
+
+    fn cookie(&self) -> String {
+        format!("session={}", self.session)
+    }
+}
+
+fn read_response(
+    url: &str,
+    response: Result<ureq::Response, ureq::Error>,
+) -> Result<String, ClientError> {
+    match response {
+        Ok(response) => response.into_string().map_err(|source| ClientError::Body {
+            url: url.to_owned(),
+            source,
+        }),
+        Err(ureq::Error::Status(status, response)) => Err(ClientError::Status {
+            url: url.to_owned(),
+            status,
+            body: response.into_string().unwrap_or_default().trim().to_owned(),
+        }),
+        Err(e) => Err(ClientError::Transport {
+            url: url.to_owned(),
+            source: Box::new(e),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Client;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    /// Serve a single canned response on a local port, returning the base URL
+    /// and a handle yielding the request that was received.
+    fn serve_once(
+        status: &'static str,
+        body: &'static str,
+    ) -> (String, thread::JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+
+        let handle = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+
+            let mut request = String::new();
+            let mut content_length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line == "\r\n" || line.is_empty() {
+                    break;
+                }
+                if let Some(len) = line.to_lowercase().strip_prefix("content-length:") {
+                    content_length = len.trim().parse().unwrap();
+                }
+                request.push_str(&line);
+            }
+
+            let mut content = vec![0; content_length];
+            reader.read_exact(&mut content).unwrap();
+            request.push_str(&String::from_utf8(content).unwrap());
+
+            write!(
+                reader.get_mut(),
+                "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                body.len(),
+                body
+            )
+            .unwrap();
+            request
+        });
+
+        (base_url, handle)
+    }
+
+    fn client(base_url: String) -> Client {
+        Client {
+            base_url,
+            year: 2021,
+            session: "abc123".to_owned(),
+            agent: ureq::agent(),
+        }
+    }
+
+    #[test]
+    fn test_fetch_input() {
+        let (base_url, server) = serve_once("200 OK", "199\n200\n");
+        let input = client(base_url).input(1).unwrap();
+        let request = server.join().unwrap();
+
+        assert_eq!(input, "199\n200\n");
+        assert!(request.starts_with("GET /2021/day/1/input HTTP/1.1"));
+        assert!(request.contains("session=abc123"));
+    }
+
+    #[test]
+    fn test_fetch_error_status() {
+        let (base_url, server) = serve_once("404 Not Found", "Not found");
+        let err = client(base_url).input(26).unwrap_err();
+        server.join().unwrap();
+
+        assert!(err.to_string().contains("404"));
+    }
+
+    #[test]
+    fn test_submit() {
+        let (base_url, server) = serve_once(
+            "200 OK",
+            "<article><p>That's the right answer!</p></article>",
+        );
+        let reply = client(base_url).submit(1, 2, "1235").unwrap();
+        let request = server.join().unwrap();
+
+        assert!(reply.contains("right answer"));
+        assert!(request.starts_with("POST /2021/day/1/answer HTTP/1.1"));
+        assert!(request.ends_with("level=2&answer=1235"));
+    }
+}
@@ -0,0 +1,236 @@
+use std::convert::Infallible;
+use std::fmt;
+use std::num::ParseIntError;
+use std::ops::Range;
+
+use thiserror::Error;
+
+/// A parse error located in the input, rendered like a compiler error:
+///
+/// ```text
+/// Invalid bit: '2'
+///  --> inputs/problem3/input.txt:4:4
+///   |
+/// 4 | 01021
+///   |    ^
+/// ```
+#[derive(Error, Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    /// Where the input came from, e.g. its path.
+    pub name: String,
+    /// 1-based line number.
+    pub lineno: usize,
+    /// Text of the offending line.
+    pub line: String,
+    /// Byte range of the offending token within the line. `None` covers the
+    /// whole line.
+    pub span: Option<Range<usize>>,
+    pub message: String,
+}
+
+impl Diagnostic {
+    /// 1-based column where the span starts. Spans may extend past the end
+    /// of the line, e.g. to point at a stripped line ending.
+    pub fn column(&self) -> usize {
+        match &self.span {
+            Some(span) => match self.line.get(..span.start) {
+                Some(before) => before.chars().count() + 1,
+                None => self.line.chars().count() + 1,
+            },
+            None => 1,
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let gutter = " ".repeat(self.lineno.to_string().len());
+        let span = self.span.clone().unwrap_or(0..self.line.len());
+        let width = self.line.get(span).map_or(1, |s| s.chars().count()).max(1);
+
+        writeln!(f, "{}", self.message)?;
+        writeln!(
+            f,
+            "{}--> {}:{}:{}",
+            gutter,
+            self.name,
+            self.lineno,
+            self.column()
+        )?;
+        writeln!(f, "{} |", gutter)?;
+        writeln!(f, "{} | {}", self.lineno, self.line)?;
+        write!(
+            f,
+            "{} | {}{}",
+            gutter,
+            " ".repeat(self.column() - 1),
+            "^".repeat(width)
+        )
+    }
+}
+
+/// Every error found while parsing an input, so that they can all be fixed
+/// in one pass.
+#[derive(Error, Debug, Clone, PartialEq)]
+pub struct Diagnostics(pub Vec<Diagnostic>);
+
+impl Diagnostics {
+    /// Most diagnostics to render, so that e.g. a file with the wrong line
+    /// endings doesn't produce one for every line.
+    const MAX_SHOWN: usize = 10;
+}
+
+impl fmt::Display for Diagnostics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let [diagnostic] = self.0.as_slice() {
+            return write!(f, "{}", diagnostic);
+        }
+
+        write!(f, "{} lines failed to parse", self.0.len())?;
+        for diagnostic in self.0.iter().take(Self::MAX_SHOWN) {
+            write!(f, "\n\n{}", diagnostic)?;
+        }
+        if self.0.len() > Self::MAX_SHOWN {
+            write!(f, "\n\n...and {} more", self.0.len() - Self::MAX_SHOWN)?;
+        }
+        Ok(())
+    }
+}
+
+/// Whether a parser may skip input it can't parse, whether it may fix
+/// formatting problems like `\r\n` line endings, and the warnings for
+/// anything it skipped.
+#[derive(Debug, Default)]
+pub struct Leniency {
+    lenient: bool,
+    strict: bool,
+    warnings: Vec<Diagnostic>,
+}
+
+impl Leniency {
+    pub fn new(lenient: bool) -> Leniency {
+        Leniency {
+            lenient,
+            strict: false,
+            warnings: Vec::new(),
+        }
+    }
+
+    /// Report formatting problems as errors rather than fixing them.
+    pub fn with_strict(mut self, strict: bool) -> Leniency {
+        self.strict = strict;
+        self
+    }
+
+    pub fn is_lenient(&self) -> bool {
+        self.lenient
+    }
+
+    pub fn is_strict(&self) -> bool {
+        self.strict
+    }
+
+    /// Handle the errors found while parsing: fail with all of them, or, if
+    /// lenient, record them as warnings so parsing can go on without the
+    /// offending input.
+    pub fn check(&mut self, errors: Vec<Diagnostic>) -> Result<(), Diagnostics> {
+        match errors.is_empty() {
+            true => Ok(()),
+            false if self.lenient => {
+                self.warnings.extend(errors);
+                Ok(())
+            }
+            false => Err(Diagnostics(errors)),
+        }
+    }
+
+    pub fn warnings(&self) -> &[Diagnostic] {
+        &self.warnings
+    }
+
+    pub fn into_warnings(self) -> Vec<Diagnostic> {
+        self.warnings
+    }
+}
+
+/// Parse errors that know which part of their input was at fault.
+pub trait Spanned {
+    /// Byte range of the offending token within the line, or `None` if the
+    /// line as a whole is at fault.
+    fn span(&self) -> Option<Range<usize>> {
+        None
+    }
+
+    /// 0-based line at fault, for errors from parsing several lines at once.
+    fn line(&self) -> usize {
+        0
+    }
+}
+
+impl Spanned for ParseIntError {}
+
+impl Spanned for Infallible {}
+
+/// Byte range of `inner` within `outer`, which it must be a slice of.
+pub fn span_of(outer: &str, inner: &str) -> Range<usize> {
+    let start = inner.as_ptr() as usize - outer.as_ptr() as usize;
+    debug_assert!(start + inner.len() <= outer.len());
+    start..start + inner.len()
+}
+
+/// An error's message followed by those of its sources, like anyhow's `{:#}`.
+pub fn message_chain(error: &dyn std::error::Error) -> String {
+    let mut message = error.to_string();
+    let mut source = error.source();
+    while let Some(e) = source {
+        message.push_str(&format!(": {}", e));
+        source = e.source();
+    }
+    message
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{span_of, Diagnostic, Leniency};
+
+    #[test]
+    fn test_render() {
+        let line = "0,9 -> 5,x";
+        let diagnostic = Diagnostic {
+            name: "input.txt".to_owned(),
+            lineno: 12,
+            line: line.to_owned(),
+            span: Some(span_of(line, &line[7..])),
+            message: "Failed to parse int pair".to_owned(),
+        };
+
+        assert_eq!(
+            diagnostic.to_string(),
+            "Failed to parse int pair
+  --> input.txt:12:8
+   |
+12 | 0,9 -> 5,x
+   |        ^^^"
+        );
+    }
+
+    #[test]
+    fn test_leniency() {
+        let diagnostic = Diagnostic {
+            name: "input.txt".to_owned(),
+            lineno: 1,
+            line: "x".to_owned(),
+            span: None,
+            message: "bad".to_owned(),
+        };
+
+        let mut strict = Leniency::new(false);
+        assert!(strict.check(vec![]).is_ok());
+        let err = strict.check(vec![diagnostic.clone(), diagnostic.clone()]);
+        assert_eq!(err.unwrap_err().0.len(), 2);
+
+        let mut lenient = Leniency::new(true);
+        assert!(lenient.check(vec![diagnostic.clone()]).is_ok());
+        assert_eq!(lenient.into_warnings(), vec![diagnostic]);
+    }
+}
@@ -0,0 +1,67 @@
+use std::path::Path;
+
+use anyhow::Context;
+use structopt::StructOpt;
+
+use crate::client::ClientOpt;
+use crate::input::default_input_path;
+use crate::solver::Day;
+use crate::utils::ProblemResult;
+
+#[derive(StructOpt, Debug)]
+pub struct Fetch {
+    /// Day to fetch, which need not be registered yet. Fetches every
+    /// registered day if omitted.
+    #[structopt(long = "day")]
+    day: Option<u32>,
+
+    #[structopt(flatten)]
+    client: ClientOpt,
+}
+
+impl Fetch {
+    pub fn run(&self, days: &[Day]) -> ProblemResult {
+        let to_fetch: Vec<u32> = match self.day {
+            Some(day) => vec![day],
+            None => days.iter().map(|d| d.day).collect(),
+        };
+
+        // Only require a session if something actually needs downloading.
+        let mut client = None;
+
+        for day in to_fetch {
+            let path = default_input_path(day);
+            if Path::new(&path).exists() {
+                println!("Day {}: cached at {}", day, path);
+                continue;
+            }
+
+            let client = match &mut client {
+                Some(c) => c,
+                None => client.insert(self.client.client()?),
+            };
+            let input = client.input(day)?;
+            write_new(&path, &input)?;
+            println!("Day {}: downloaded to {}", day, path);
+        }
+
+        Ok(())
+    }
+}
+
+/// Write a file via a temporary sibling, so an interrupted write never leaves
+/// a partial input that would later be mistaken for a cached one.
+fn write_new(path: &str, contents: &str) -> ProblemResult {
+    let path = Path::new(path);
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create directory {}", dir.display()))?;
+    }
+
+    let tmp = path.with_extension("tmp");
+    std::fs::write(&tmp, contents).with_context(|| format!("Failed to write {}", tmp.display()))?;
+    std::fs::rename(&tmp, path)
+        .with_context(|| format!("Failed to move {} into place", tmp.display()))?;
+
+    Ok(())
+}
@@ -0,0 +1,204 @@
+use std::fs::OpenOptions;
+use std::io::{BufRead, BufReader, Write};
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::bench::Phase;
+use crate::input::InputSource;
+
+/// A saved benchmark result for one phase of one day.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Entry {
+    /// Seconds since the Unix epoch when the benchmark ran.
+    pub timestamp: u64,
+    /// Git revision of the benchmarked code, suffixed with `-dirty` if the
+    /// working tree had uncommitted changes.
+    pub revision: String,
+    /// Hash of the input contents, if the input could be hashed.
+    pub input_hash: Option<String>,
+    pub day: u32,
+    pub phase: Phase,
+    pub iterations: usize,
+    pub median_ns: u64,
+    pub mean_ns: u64,
+}
+
+#[derive(Error, Debug)]
+pub enum HistoryError {
+    #[error("Failed to read benchmark history: {path}")]
+    Read {
+        path: String,
+        source: std::io::Error,
+    },
+
+    #[error("Failed to parse benchmark history {path} at line {lineno}")]
+    Parse {
+        path: String,
+        lineno: usize,
+        source: serde_json::Error,
+    },
+
+    #[error("Failed to write benchmark history: {path}")]
+    Write {
+        path: String,
+        source: std::io::Error,
+    },
+}
+
+/// Load every entry from a history file, which holds one JSON entry per line.
+/// A missing file is an empty history.
+pub fn load(path: &str) -> Result<Vec<Entry>, HistoryError> {
+    let read_err = |source| HistoryError::Read {
+        path: path.to_owned(),
+        source,
+    };
+
+    let file = match std::fs::File::open(path) {
+        Ok(f) => f,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(read_err(e)),
+    };
+
+    let mut entries = Vec::new();
+    for (lineno, line) in BufReader::new(file).lines().enumerate() {
+        let line = line.map_err(read_err)?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let entry = serde_json::from_str(&line).map_err(|source| HistoryError::Parse {
+            path: path.to_owned(),
+            lineno: lineno + 1,
+            source,
+        })?;
+        entries.push(entry);
+    }
+
+    Ok(entries)
+}
+
+/// Append entries to a history file, creating it if needed.
+pub fn append(path: &str, entries: &[Entry]) -> Result<(), HistoryError> {
+    let write_err = |source| HistoryError::Write {
+        path: path.to_owned(),
+        source,
+    };
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(write_err)?;
+
+    for entry in entries {
+        let line = serde_json::to_string(entry).expect("history entries are serializable");
+        writeln!(file, "{}", line).map_err(write_err)?;
+    }
+
+    Ok(())
+}
+
+/// The most recent entry for the same day, phase and input, optionally
+/// restricted to a single revision.
+pub fn baseline<'a>(
+    history: &'a [Entry],
+    current: &Entry,
+    revision: Option<&str>,
+) -> Option<&'a Entry> {
+    history
+        .iter()
+        .filter(|e| {
+            e.day == current.day && e.phase == current.phase && e.input_hash == current.input_hash
+        })
+        .filter(|e| revision.is_none_or(|r| e.revision == r))
+        .max_by_key(|e| e.timestamp)
+}
+
+/// The current git revision, or `unknown` outside a git checkout.
+pub fn git_revision() -> String {
+    let git = |args: &[&str]| -> Option<String> {
+        let output = Command::new("git").args(args).output().ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        Some(String::from_utf8_lossy(&output.stdout).trim().to_owned())
+    };
+
+    match git(&["rev-parse", "--short", "HEAD"]) {
+        Some(rev) => match git(&["status", "--porcelain", "--untracked-files=no"]) {
+            Some(status) if !status.is_empty() => format!("{}-dirty", rev),
+            _ => rev,
+        },
+        None => "unknown".to_owned(),
+    }
+}
+
+/// A stable hash of an input's contents. Stdin can only be read once, so it
+/// isn't hashed.
+pub fn input_hash(source: &InputSource) -> Option<String> {
+    let contents = match source {
+        InputSource::Stdin => return None,
+        _ => source.read_to_string().ok()?,
+    };
+    Some(format!("{:016x}", fnv1a(contents.as_bytes())))
+}
+
+/// 64-bit FNV-1a, used because std's hashers aren't stable across releases.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, &b| {
+        (hash ^ b as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{baseline, fnv1a, Entry};
+    use crate::bench::Phase;
+
+    #[test]
+    fn test_fnv1a() {
+        assert_eq!(fnv1a(b""), 0xcbf29ce484222325);
+        assert_eq!(fnv1a(b"a"), 0xaf63dc4c8601ec8c);
+    }
+
+    #[test]
+    fn test_baseline() {
+        let entry = |timestamp, revision: &str, hash: &str, day, phase| Entry {
+            timestamp,
+            revision: revision.to_owned(),
+            input_hash: Some(hash.to_owned()),
+            day,
+            phase,
+            iterations: 10,
+            median_ns: timestamp * 100,
+            mean_ns: timestamp * 100,
+        };
+        let history = vec![
+            entry(1, "aaa", "in", 1, Phase::Part1),
+            entry(3, "bbb", "in", 1, Phase::Part1),
+            entry(2, "aaa", "in", 1, Phase::Part1),
+            entry(4, "ccc", "other", 1, Phase::Part1),
+            entry(5, "ccc", "in", 2, Phase::Part1),
+            entry(6, "ccc", "in", 1, Phase::Part2),
+        ];
+        let current = entry(7, "ddd", "in", 1, Phase::Part1);
+
+        let found = |revision| baseline(&history, &current, revision).map(|e| e.timestamp);
+        // Only the first three share the day, phase and input.
+        assert_eq!(found(None), Some(3));
+        assert_eq!(found(Some("aaa")), Some(2));
+        assert_eq!(found(Some("ccc")), None);
+
+        let other_input = entry(7, "ddd", "new", 1, Phase::Part1);
+        assert!(baseline(&history, &other_input, None).is_none());
+    }
+}
@@ -0,0 +1,208 @@
+use std::fmt;
+use std::fs::File;
+use std::io::{Error as IOError, Read};
+use std::ops::Range;
+
+use structopt::StructOpt;
+use thiserror::Error;
+
+use crate::diagnostic::{Diagnostic, Leniency};
+
+/// Input options shared by every day.
+#[derive(StructOpt, Debug)]
+pub struct InputOpt {
+    /// Input file, or `-` to read from stdin. Defaults to
+    /// `inputs/problemN/input.txt` for day N.
+    #[structopt(short = "i", long = "input")]
+    input: Option<String>,
+
+    /// Literal puzzle input, used instead of reading a file.
+    #[structopt(long = "input-text", conflicts_with = "input")]
+    input_text: Option<String>,
+
+    /// Skip input that fails to parse, reporting it as warnings, instead of
+    /// failing.
+    #[structopt(long = "lenient")]
+    lenient: bool,
+
+    /// Report `\r\n` line endings, trailing whitespace and blank lines at
+    /// the end of the input as errors instead of fixing them.
+    #[structopt(long = "strict", conflicts_with = "lenient")]
+    strict: bool,
+
+    /// Set in code to read input from somewhere the options can't name.
+    #[structopt(skip)]
+    source: Option<InputSource>,
+}
+
+impl InputOpt {
+    /// Where to read input for the given day.
+    pub fn source(&self, day: u32) -> InputSource {
+        if let Some(source) = &self.source {
+            return source.clone();
+        }
+        match (&self.input, &self.input_text) {
+            (_, Some(text)) => InputSource::Text(text.clone()),
+            (Some(path), None) if path == "-" => InputSource::Stdin,
+            (Some(path), None) => InputSource::Path(path.clone()),
+            (None, None) => InputSource::Path(default_input_path(day)),
+        }
+    }
+
+    /// Read input from `source`, overriding `--input` and `--input-text`.
+    pub fn set_source(&mut self, source: InputSource) {
+        self.source = Some(source);
+    }
+
+    /// How forgiving to be of malformed input.
+    pub fn leniency(&self) -> Leniency {
+        Leniency::new(self.lenient).with_strict(self.strict)
+    }
+}
+
+/// Path of a day's input when none is given.
+pub fn default_input_path(day: u32) -> String {
+    format!("inputs/problem{}/input.txt", day)
+}
+
+/// Where puzzle input is read from.
+#[derive(Debug, Clone, PartialEq)]
+pub enum InputSource {
+    Path(String),
+    Stdin,
+    Text(String),
+}
+
+#[derive(Error, Debug)]
+pub enum InputError {
+    #[error("Failed to open file: {path}")]
+    FileOpen { path: String, source: IOError },
+
+    #[error("Failed to read input from {name}")]
+    Read { name: String, source: IOError },
+}
+
+impl InputSource {
+    pub fn open(&self) -> Result<Box<dyn Read + '_>, InputError> {
+        match self {
+            InputSource::Path(path) => match File::open(path) {
+                Ok(f) => Ok(Box::new(f)),
+                Err(source) => Err(InputError::FileOpen {
+                    path: path.clone(),
+                    source,
+                }),
+            },
+            InputSource::Stdin => Ok(Box::new(std::io::stdin())),
+            InputSource::Text(text) => Ok(Box::new(text.as_bytes())),
+        }
+    }
+
+    pub fn read_to_string(&self) -> Result<String, InputError> {
+        let mut buf = String::new();
+        self.open()?
+            .read_to_string(&mut buf)
+            .map_err(|source| InputError::Read {
+                name: self.to_string(),
+                source,
+            })?;
+        Ok(buf)
+    }
+}
+
+/// A formatting problem in a line of input, fixed when loading it unless in
+/// strict mode.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Issue {
+    pub kind: IssueKind,
+    /// Byte range of the offending characters within the line.
+    pub span: Range<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum IssueKind {
+    CarriageReturn,
+    TrailingWhitespace,
+    TrailingBlankLines,
+}
+
+impl Issue {
+    pub fn diagnostic(&self, name: &dyn fmt::Display, lineno: usize, line: &str) -> Diagnostic {
+        let message = match self.kind {
+            IssueKind::CarriageReturn => "Line ends with \\r\\n",
+            IssueKind::TrailingWhitespace => "Trailing whitespace",
+            IssueKind::TrailingBlankLines => "Blank lines at end of input",
+        };
+        Diagnostic {
+            name: name.to_string(),
+            lineno,
+            line: line.trim_end_matches('\r').to_owned(),
+            span: Some(self.span.clone()),
+            message: message.to_owned(),
+        }
+    }
+}
+
+/// Strip a line's trailing whitespace, including the `\r` of a `\r\n` line
+/// ending, returning what was stripped as an issue.
+pub fn normalize_line(line: &str) -> (&str, Option<Issue>) {
+    let trimmed = line.trim_end();
+    if trimmed.len() == line.len() {
+        return (line, None);
+    }
+
+    let kind = match &line[trimmed.len()..] {
+        "\r" => IssueKind::CarriageReturn,
+        _ => IssueKind::TrailingWhitespace,
+    };
+    let issue = Issue {
+        kind,
+        span: trimmed.len()..line.len(),
+    };
+    (trimmed, Some(issue))
+}
+
+impl fmt::Display for InputSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InputSource::Path(path) => write!(f, "{}", path),
+            InputSource::Stdin => write!(f, "<stdin>"),
+            InputSource::Text(_) => write!(f, "<input text>"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{normalize_line, InputOpt, InputSource, Issue, IssueKind};
+    use structopt::StructOpt;
+
+    #[test]
+    fn test_source() {
+        let source = |args: &[&str]| InputOpt::from_iter(args).source(3);
+
+        assert_eq!(
+            source(&["p3"]),
+            InputSource::Path("inputs/problem3/input.txt".to_owned())
+        );
+        assert_eq!(source(&["p3", "-i", "-"]), InputSource::Stdin);
+        assert_eq!(
+            source(&["p3", "--input-text", "101"]),
+            InputSource::Text("101".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_normalize_line() {
+        assert_eq!(normalize_line("forward 5"), ("forward 5", None));
+
+        let issue = |kind, span| Some(Issue { kind, span });
+        assert_eq!(
+            normalize_line("forward 5\r"),
+            ("forward 5", issue(IssueKind::CarriageReturn, 9..10))
+        );
+        assert_eq!(
+            normalize_line("forward 5 \t\r"),
+            ("forward 5", issue(IssueKind::TrailingWhitespace, 9..12))
+        );
+    }
+}
@@ -1,42 +1,152 @@
-mod problem1;
-mod problem2;
-mod problem3;
-mod problem4;
-mod problem5;
+mod bench;
+mod client;
+mod diagnostic;
+mod fetch;
+mod history;
+mod input;
+mod report;
+mod scaffold;
+mod solver;
+mod sonar;
+mod submit;
 mod utils;
+mod verify;
 
+use crate::report::{Format, Outcome};
+use crate::solver::{Day, Parts};
 use crate::utils::ProblemResult;
 
+use anyhow::anyhow;
+use structopt::clap::AppSettings;
 use structopt::StructOpt;
 
+/// Declare each day's module and register its solver. Adding a day only
+/// requires adding its `module::Solver` path here, which `new-day` does.
+macro_rules! days {
+    ($($module:ident :: $solver:ident),* $(,)?) => {
+        $(mod $module;)*
+
+        fn registry() -> Vec<Day> {
+            vec![$(Day::new::<$module::$solver>()),*]
+        }
+    };
+}
+
+days! {
+    problem1::P1,
+    problem2::P2,
+    problem3::P3,
+    problem4::P4,
+    problem5::P5,
+}
+
+// Global options and subcommands other than the per-day `pN` commands, which
+// are added from the registry.
+#[derive(StructOpt, Debug)]
+#[structopt(setting = AppSettings::SubcommandRequiredElseHelp)]
+struct Opt {
+    /// Output format.
+    #[structopt(
+        long = "format",
+        global = true,
+        default_value = "text",
+        possible_values = &Format::VARIANTS
+    )]
+    format: Format,
+
+    /// Only run this part of each problem.
+    #[structopt(long = "part", global = true, possible_values = &["1", "2"])]
+    part: Option<u8>,
+
+    #[structopt(subcommand)]
+    command: Option<Command>,
+}
+
 #[derive(StructOpt, Debug)]
 #[structopt(rename_all = "kebab-case")]
-enum Opt {
-    /// Run problem 1.
-    P1(problem1::P1),
-    /// Run problem 2.
-    P2(problem2::P2),
-    /// Run problem 3.
-    P3(problem3::P3),
-    /// Run problem 4.
-    P4(problem4::P4),
-    /// Run problem 5.
-    P5(problem5::P5),
+enum Command {
+    /// Run every problem with its default input and print a table of results.
+    All,
+    /// Time each problem's parsing and parts over repeated runs.
+    Bench(bench::Bench),
+    /// Download puzzle inputs that aren't already saved locally.
+    Fetch(fetch::Fetch),
+    /// Generate and register a new day's module.
+    NewDay(scaffold::NewDay),
+    /// Run day 1's analysis over a live feed of depths, e.g. a growing file.
+    Sonar(sonar::Sonar),
+    /// Submit a computed answer for one part of a day.
+    Submit(submit::Submit),
+    /// Check every problem's answers against a file of known-correct answers.
+    Verify(verify::Verify),
 }
 
-impl Opt {
-    fn run(&self) -> ProblemResult {
+impl Command {
+    fn run(&self, days: &[Day], parts: Parts, format: Format) -> ProblemResult {
         match self {
-            Opt::P1(p) => p.run(),
-            Opt::P2(p) => p.run(),
-            Opt::P3(p) => p.run(),
-            Opt::P4(p) => p.run(),
-            Opt::P5(p) => p.run(),
+            Command::All => run_all(days, parts, format),
+            Command::Bench(b) => b.run(days, parts, format),
+            Command::Fetch(f) => f.run(days),
+            Command::NewDay(n) => n.run(),
+            Command::Sonar(s) => s.run(parts, format),
+            Command::Submit(s) => s.run(days, parts),
+            Command::Verify(v) => v.run(days, parts, format),
         }
     }
 }
 
+fn run_all(days: &[Day], parts: Parts, format: Format) -> ProblemResult {
+    let outcomes: Vec<Outcome> = days
+        .iter()
+        .map(|day| Outcome {
+            day: day.day,
+            parts,
+            solution: day.with_defaults().solve(parts),
+        })
+        .collect();
+
+    report::print_days(format, &outcomes)?;
+
+    let failures = outcomes.iter().filter(|o| o.failed()).count();
+    if failures > 0 {
+        return Err(anyhow!("{} of {} days failed", failures, outcomes.len()));
+    }
+
+    Ok(())
+}
+
 fn main() -> ProblemResult {
-    let opt = Opt::from_args();
-    opt.run()
+    let days = registry();
+
+    let app = days
+        .iter()
+        .fold(Opt::clap(), |app, day| app.subcommand(day.subcommand()));
+    let matches = app.get_matches();
+    let opt = Opt::from_clap(&matches);
+    let parts = Parts::from(opt.part);
+
+    if let Some(command) = &opt.command {
+        return command.run(&days, parts, opt.format);
+    }
+
+    let (name, sub_matches) = matches.subcommand();
+    let day = days
+        .iter()
+        .find(|day| day.name() == name)
+        .expect("clap only accepts registered subcommands");
+
+    let outcome = Outcome {
+        day: day.day,
+        parts,
+        solution: day
+            .build(sub_matches.expect("subcommand matched"))
+            .solve(parts),
+    };
+    report::print_day(opt.format, &outcome)?;
+
+    match outcome.solution {
+        Err(e) => Err(e),
+        Ok(_) if outcome.failed() => Err(anyhow!("Day {} failed", day.day)),
+        Ok(_) => Ok(()),
+    }
 }
@@ -109,44 +109,567 @@
 ///
 /// Consider sums of a three-measurement sliding window. How many sums are
 /// larger than the previous sum?
+use std::collections::VecDeque;
+use std::convert::TryFrom;
+use std::str::FromStr;
 
+use anyhow::anyhow;
+use aoc_derive::PuzzleParse;
 use structopt::StructOpt;
+use thiserror::Error;
 
-use crate::utils::{parse_lines_from_path, ProblemResult};
+use crate::diagnostic::{message_chain, Leniency};
+use crate::input::InputOpt;
+use crate::solver::Solver;
+use crate::utils::{LineValues, ProblemResult};
 
 #[derive(StructOpt, Debug)]
 pub struct P1 {
+    #[structopt(flatten)]
+    input: InputOpt,
+
+    /// Read depths lazily while solving instead of loading them up front.
+    #[structopt(long = "stream")]
+    stream: bool,
+
+    #[structopt(flatten)]
+    analysis: Analysis,
+
+    /// Also print statistics of the depths: runs, extremes, a histogram of
+    /// changes and a sparkline of the depth profile.
+    #[structopt(long = "report")]
+    report: bool,
+
+    /// How to count increases of window sums. `shifted` and `parallel` are
+    /// faster on very large inputs, but need loaded depths and the default
+    /// `--aggregate`, `--compare` and no `--filter`.
     #[structopt(
-        short = "i",
-        long = "input",
-        default_value = "inputs/problem1/input.txt"
+        long = "method",
+        default_value = "reference",
+        possible_values = &Method::VARIANTS
     )]
-    input: String,
+    method: Method,
+}
+
+impl Solver for P1 {
+    const DAY: u32 = 1;
+    const TITLE: &'static str = "Sonar Sweep";
+
+    type Input = LineValues<u32>;
+    type Part1 = usize;
+    type Part2 = usize;
+
+    fn input(&self) -> &InputOpt {
+        &self.input
+    }
+
+    fn input_mut(&mut self) -> &mut InputOpt {
+        &mut self.input
+    }
+
+    fn parse(&self, leniency: &mut Leniency) -> ProblemResult<LineValues<u32>> {
+        LineValues::ints_from_source(self.source(), self.stream, leniency)
+    }
+
+    fn part1(&self, depths: &LineValues<u32>) -> ProblemResult<usize> {
+        match self.loaded(depths)? {
+            Some(depths) => Ok(self.method.count(depths, 1)),
+            None => depths.process(|it| part1(it, &self.analysis)),
+        }
+    }
+
+    fn part2(&self, depths: &LineValues<u32>) -> ProblemResult<usize> {
+        let window = self.analysis.window()?;
+        match self.loaded(depths)? {
+            Some(depths) => Ok(self.method.count(depths, window)),
+            None => depths.process(|it| part2(it, &self.analysis)),
+        }
+    }
+
+    fn details(&self, depths: &LineValues<u32>) -> ProblemResult<Option<String>> {
+        match self.report {
+            true => Ok(Some(report(depths)?)),
+            false => Ok(None),
+        }
+    }
 }
 
 impl P1 {
-    pub fn run(&self) -> ProblemResult {
-        let depths: Vec<u32> = parse_lines_from_path(&self.input)?;
+    /// The depths for `--method` to count, or `None` to count them with the
+    /// full analysis.
+    fn loaded<'a>(&self, depths: &'a LineValues<u32>) -> ProblemResult<Option<&'a [u32]>> {
+        if self.method == Method::Reference {
+            return Ok(None);
+        }
+        if !self.analysis.sums_increases() {
+            return Err(anyhow!(
+                "--method only counts increases of unfiltered window sums"
+            ));
+        }
+        match depths {
+            LineValues::Loaded { values, .. } => Ok(Some(values)),
+            LineValues::Streamed { .. } => Err(anyhow!("--method needs loaded depths, not --stream")),
+        }
+    }
+}
+
+/// How a trace of measurements is reduced to a count of changes. The
+/// defaults answer the puzzle.
+#[derive(StructOpt, Debug, Clone)]
+pub struct Analysis {
+    /// Number of measurements in each sliding window for part 2. A window of
+    /// 1 compares single measurements, as part 1 does.
+    #[structopt(long = "window", default_value = "3")]
+    window: usize,
 
-        let (incs, rolling_incs) = run_problem(&depths)?;
+    /// How to combine the measurements in a window.
+    #[structopt(
+        long = "aggregate",
+        default_value = "sum",
+        possible_values = &Aggregate::VARIANTS
+    )]
+    aggregate: Aggregate,
 
-        println!("Part 1: {}", incs);
-        println!("Part 2: {}", rolling_incs);
+    /// Which changes between consecutive windows to count. `change` counts
+    /// changes in either direction larger than `--threshold`.
+    #[structopt(
+        long = "compare",
+        default_value = "increase",
+        possible_values = &Comparison::VARIANTS
+    )]
+    compare: Comparison,
 
-        Ok(())
+    /// Smallest change not counted by `--compare change`, in the units of
+    /// the aggregated values.
+    #[structopt(long = "threshold", default_value = "0")]
+    threshold: f64,
+
+    /// Noise filter to pass depths through before windowing them; repeat to
+    /// chain filters in order. `mean:N` and `median:N` are the mean and
+    /// median of the last N depths, `ema:ALPHA` an exponential moving
+    /// average, and `zscore:Z:N` drops depths more than Z standard deviations
+    /// from the mean of the N depths before.
+    #[structopt(
+        long = "filter",
+        number_of_values = 1,
+        parse(try_from_str = parse_filter)
+    )]
+    filters: Vec<Filter>,
+}
+
+impl Default for Analysis {
+    fn default() -> Analysis {
+        Analysis {
+            window: 3,
+            aggregate: Aggregate::Sum,
+            compare: Comparison::Increase,
+            threshold: 0.0,
+            filters: Vec::new(),
+        }
+    }
+}
+
+impl Analysis {
+    /// The size of part 2's window, which must hold at least one depth.
+    pub fn window(&self) -> ProblemResult<usize> {
+        match self.window {
+            0 => Err(anyhow!("The window must hold at least one measurement")),
+            size => Ok(size),
+        }
+    }
+
+    /// Count the changes between consecutive windows of `size` filtered
+    /// depths.
+    fn count(&self, depths: impl Iterator<Item = u32>, size: usize) -> usize {
+        let mut pipeline = Pipeline::new(&self.filters);
+        let filtered = depths.filter_map(move |depth| pipeline.push(depth));
+        let values = windows(filtered, size, self.aggregate);
+        self.compare.count(values, self.threshold(size))
+    }
+
+    /// Whether this counts increases of window sums (or means, which rise
+    /// and fall with them), as the puzzle does.
+    fn sums_increases(&self) -> bool {
+        matches!(self.aggregate, Aggregate::Sum | Aggregate::Mean)
+            && self.compare == Comparison::Increase
+            && self.filters.is_empty()
+    }
+
+    /// The threshold in the units of values aggregated from `size` depths.
+    fn threshold(&self, size: usize) -> f64 {
+        self.threshold * self.aggregate.scale(size) as f64 * PRECISION
+    }
+}
+
+fn part1(depths: impl Iterator<Item = u32>, analysis: &Analysis) -> usize {
+    analysis.count(depths, 1)
+}
+
+fn part2(depths: impl Iterator<Item = u32>, analysis: &Analysis) -> usize {
+    analysis.count(depths, analysis.window)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Aggregate {
+    Sum,
+    Mean,
+    Min,
+    Max,
+    Median,
+}
+
+impl Aggregate {
+    pub const VARIANTS: [&'static str; 5] = ["sum", "mean", "min", "max", "median"];
+
+    /// Combine a window whose measurements add up to `sum`. Values are kept
+    /// whole by scaling them up by `scale`: means are left as sums, and the
+    /// median of an even-sized window as the sum of its middle two.
+    fn apply(self, window: &VecDeque<u64>, sum: u64, sorted: &mut Vec<u64>) -> u64 {
+        match self {
+            Aggregate::Sum | Aggregate::Mean => sum,
+            Aggregate::Min => window.iter().copied().min().unwrap_or(0),
+            Aggregate::Max => window.iter().copied().max().unwrap_or(0),
+            Aggregate::Median => {
+                sorted.clear();
+                sorted.extend(window.iter().copied());
+                sorted.sort_unstable();
+                let mid = sorted.len() / 2;
+                match sorted.len() % 2 {
+                    1 => sorted[mid],
+                    _ => sorted[mid - 1] + sorted[mid],
+                }
+            }
+        }
+    }
+
+    /// How many times larger than the true aggregate `apply`'s values are.
+    fn scale(self, size: usize) -> u64 {
+        match self {
+            Aggregate::Mean => size as u64,
+            Aggregate::Median => match size % 2 {
+                0 => 2,
+                _ => 1,
+            },
+            _ => 1,
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+#[error("Unknown aggregation: {0}")]
+pub struct UnknownAggregate(String);
+
+impl FromStr for Aggregate {
+    type Err = UnknownAggregate;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "sum" => Ok(Aggregate::Sum),
+            "mean" => Ok(Aggregate::Mean),
+            "min" => Ok(Aggregate::Min),
+            "max" => Ok(Aggregate::Max),
+            "median" => Ok(Aggregate::Median),
+            _ => Err(UnknownAggregate(s.to_owned())),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Comparison {
+    Increase,
+    Decrease,
+    Unchanged,
+    Change,
+}
+
+impl Comparison {
+    pub const VARIANTS: [&'static str; 4] = ["increase", "decrease", "unchanged", "change"];
+
+    /// Whether `prev` changing to `next` differs in this way. Only `Change`
+    /// uses `threshold`.
+    fn matches(self, prev: u64, next: u64, threshold: f64) -> bool {
+        match self {
+            Comparison::Increase => next > prev,
+            Comparison::Decrease => next < prev,
+            Comparison::Unchanged => next == prev,
+            Comparison::Change => prev.abs_diff(next) as f64 > threshold,
+        }
+    }
+
+    /// Count consecutive pairs of values that differ in this way.
+    fn count(self, values: impl Iterator<Item = u64>, threshold: f64) -> usize {
+        match self {
+            Comparison::Increase => count_increases(values),
+            _ => count_pairs(values, |&prev, &next| self.matches(prev, next, threshold)),
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+#[error("Unknown comparison: {0}")]
+pub struct UnknownComparison(String);
+
+impl FromStr for Comparison {
+    type Err = UnknownComparison;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "increase" => Ok(Comparison::Increase),
+            "decrease" => Ok(Comparison::Decrease),
+            "unchanged" => Ok(Comparison::Unchanged),
+            "change" => Ok(Comparison::Change),
+            _ => Err(UnknownComparison(s.to_owned())),
+        }
+    }
+}
+
+/// A noise filter for depths, as given to `--filter`.
+#[derive(PuzzleParse, Debug, Clone, Copy, PartialEq)]
+pub enum Filter {
+    #[pattern("mean:{0}")]
+    Mean(usize),
+    #[pattern("median:{0}")]
+    Median(usize),
+    #[pattern("ema:{0}")]
+    Ema(f64),
+    #[pattern("zscore:{0}:{1}")]
+    ZScore(f64, usize),
+}
+
+fn parse_filter(s: &str) -> Result<Filter, String> {
+    let filter: Filter = s.parse().map_err(|e| message_chain(&e))?;
+    match filter {
+        Filter::Mean(0) | Filter::Median(0) => Err("The filter's window must not be empty".into()),
+        Filter::Ema(alpha) if !(alpha > 0.0 && alpha <= 1.0) => {
+            Err("The smoothing factor must be greater than 0 and at most 1".into())
+        }
+        Filter::ZScore(z, _) if z.is_nan() || z <= 0.0 => Err("The z-score must be positive".into()),
+        Filter::ZScore(_, n) if n < 2 => {
+            Err("The z-score window must hold at least two depths".into())
+        }
+        filter => Ok(filter),
+    }
+}
+
+/// Depths are counted in thousandths, so that filtered depths keep some of
+/// their fractional part while comparisons between them stay exact.
+const PRECISION: f64 = 1000.0;
+
+/// The state of one filter as depths pass through it.
+#[derive(Debug)]
+enum Stage {
+    Mean {
+        window: VecDeque<f64>,
+        size: usize,
+    },
+    Median {
+        window: VecDeque<f64>,
+        size: usize,
+        sorted: Vec<f64>,
+    },
+    Ema {
+        alpha: f64,
+        value: Option<f64>,
+    },
+    ZScore {
+        z: f64,
+        window: VecDeque<f64>,
+        size: usize,
+    },
+}
+
+impl Stage {
+    fn new(filter: Filter) -> Stage {
+        match filter {
+            Filter::Mean(size) => Stage::Mean {
+                window: VecDeque::with_capacity(size),
+                size,
+            },
+            Filter::Median(size) => Stage::Median {
+                window: VecDeque::with_capacity(size),
+                size,
+                sorted: Vec::with_capacity(size),
+            },
+            Filter::Ema(alpha) => Stage::Ema { alpha, value: None },
+            Filter::ZScore(z, size) => Stage::ZScore {
+                z,
+                window: VecDeque::with_capacity(size),
+                size,
+            },
+        }
+    }
+
+    /// Filter the next value, or `None` if the filter holds it back: while
+    /// a window fills, or when rejecting an outlier.
+    fn push(&mut self, value: f64) -> Option<f64> {
+        match self {
+            Stage::Mean { window, size } => {
+                slide(window, *size, value);
+                (window.len() == *size).then(|| window.iter().sum::<f64>() / *size as f64)
+            }
+            Stage::Median {
+                window,
+                size,
+                sorted,
+            } => {
+                slide(window, *size, value);
+                if window.len() < *size {
+                    return None;
+                }
+                sorted.clear();
+                sorted.extend(window.iter().copied());
+                sorted.sort_unstable_by(f64::total_cmp);
+                let mid = *size / 2;
+                match *size % 2 {
+                    1 => Some(sorted[mid]),
+                    _ => Some((sorted[mid - 1] + sorted[mid]) / 2.0),
+                }
+            }
+            Stage::Ema { alpha, value: prev } => {
+                let next = match *prev {
+                    Some(prev) => *alpha * value + (1.0 - *alpha) * prev,
+                    None => value,
+                };
+                *prev = Some(next);
+                Some(next)
+            }
+            Stage::ZScore { z, window, size } => {
+                let n = window.len() as f64;
+                let mean = window.iter().sum::<f64>() / n;
+                let variance = window.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / n;
+                // Nothing is an outlier until there's a spread to judge by.
+                let outlier = window.len() > 1 && (value - mean).abs() > *z * variance.sqrt();
+                // Outliers still join the window, so that a lasting shift in
+                // depth is soon accepted rather than rejected from then on.
+                slide(window, *size, value);
+                (!outlier).then_some(value)
+            }
+        }
+    }
+}
+
+/// Add a value to a window holding at most `size` values.
+fn slide(window: &mut VecDeque<f64>, size: usize, value: f64) {
+    if window.len() == size {
+        window.pop_front();
+    }
+    window.push_back(value);
+}
+
+/// A chain of filters, fed one depth at a time.
+#[derive(Debug)]
+struct Pipeline(Vec<Stage>);
+
+impl Pipeline {
+    fn new(filters: &[Filter]) -> Pipeline {
+        Pipeline(filters.iter().map(|&f| Stage::new(f)).collect())
+    }
+
+    /// Pass a depth through each filter in turn, returning it in units of
+    /// `1 / PRECISION`, or `None` if a filter held it back.
+    fn push(&mut self, depth: u32) -> Option<u64> {
+        let mut value = depth as f64;
+        for stage in &mut self.0 {
+            value = stage.push(value)?;
+        }
+        Some((value * PRECISION).round() as u64)
     }
 }
 
-fn run_problem(depths: &Vec<u32>) -> ProblemResult<(usize, usize)> {
-    let raw_increases = count_increases(depths.iter().cloned());
+/// The last `size` depths, held in a ring buffer along with their sum.
+#[derive(Debug)]
+struct Window {
+    depths: VecDeque<u64>,
+    size: usize,
+    sum: u64,
+    /// Scratch space for finding medians.
+    sorted: Vec<u64>,
+}
+
+impl Window {
+    fn new(size: usize) -> Window {
+        Window {
+            depths: VecDeque::with_capacity(size),
+            size,
+            sum: 0,
+            sorted: Vec::with_capacity(size),
+        }
+    }
+
+    /// Add a depth, evicting the oldest once the window is full, and return
+    /// the window's aggregate if it's full.
+    fn push(&mut self, depth: u64, aggregate: Aggregate) -> Option<u64> {
+        if self.depths.len() == self.size {
+            self.sum -= self.depths.pop_front().expect("window is non-empty");
+        }
+        self.depths.push_back(depth);
+        self.sum += depth;
+        (self.depths.len() == self.size)
+            .then(|| aggregate.apply(&self.depths, self.sum, &mut self.sorted))
+    }
+}
+
+/// Aggregates of each `size`-measurement sliding window, holding only one
+/// window in memory at a time.
+fn windows(
+    depths: impl Iterator<Item = u64>,
+    size: usize,
+    aggregate: Aggregate,
+) -> impl Iterator<Item = u64> {
+    let mut window = Window::new(size);
+    depths.filter_map(move |depth| window.push(depth, aggregate))
+}
+
+/// A count of changes between windows, updated one depth at a time in
+/// constant memory.
+#[derive(Debug)]
+pub struct RunningCount {
+    pipeline: Pipeline,
+    window: Window,
+    aggregate: Aggregate,
+    compare: Comparison,
+    threshold: f64,
+    prev: Option<u64>,
+    count: usize,
+}
+
+impl RunningCount {
+    /// Count changes between windows of `size` depths, as `analysis` says.
+    pub fn new(analysis: &Analysis, size: usize) -> RunningCount {
+        RunningCount {
+            pipeline: Pipeline::new(&analysis.filters),
+            window: Window::new(size),
+            aggregate: analysis.aggregate,
+            compare: analysis.compare,
+            threshold: analysis.threshold(size),
+            prev: None,
+            count: 0,
+        }
+    }
 
-    let rolling_sums = depths.windows(3).map(|window| window.iter().sum());
-    let rolling_sum_increases = count_increases(rolling_sums);
+    pub fn push(&mut self, depth: u32) {
+        let filtered = match self.pipeline.push(depth) {
+            Some(filtered) => filtered,
+            None => return,
+        };
+        if let Some(value) = self.window.push(filtered, self.aggregate) {
+            if let Some(prev) = self.prev.replace(value) {
+                self.count += self.compare.matches(prev, value, self.threshold) as usize;
+            }
+        }
+    }
 
-    Ok((raw_increases, rolling_sum_increases))
+    pub fn count(&self) -> usize {
+        self.count
+    }
 }
 
-fn count_increases(mut it: impl Iterator<Item = u32>) -> usize {
+fn count_increases<T: Ord>(it: impl Iterator<Item = T>) -> usize {
+    count_pairs(it, |prev, next| next > prev)
+}
+
+/// Count the consecutive pairs of items for which `counted` holds.
+fn count_pairs<T>(mut it: impl Iterator<Item = T>, counted: impl Fn(&T, &T) -> bool) -> usize {
     let mut count = 0;
     let mut prev = match it.next() {
         Some(i) => i,
@@ -154,7 +677,7 @@ fn count_increases(mut it: impl Iterator<Item = u32>) -> usize {
     };
 
     for i in it {
-        if i > prev {
+        if counted(&prev, &i) {
             count += 1;
         }
         prev = i;
@@ -163,10 +686,300 @@ fn count_increases(mut it: impl Iterator<Item = u32>) -> usize {
     count
 }
 
+/// How `P1` counts increases between window sums of loaded depths.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Method {
+    /// Sum each window and compare consecutive sums.
+    Reference,
+    /// Compare depths `size` apart in a loop the compiler can vectorize.
+    Shifted,
+    /// Run the shifted comparison over chunks of the depths on every core.
+    Parallel,
+}
+
+impl Method {
+    pub const VARIANTS: [&'static str; 3] = ["reference", "shifted", "parallel"];
+
+    /// Count the increases between consecutive sums of `size` depths.
+    fn count(self, depths: &[u32], size: usize) -> usize {
+        match self {
+            Method::Reference => count_increases(windows(
+                depths.iter().map(|&d| d as u64),
+                size,
+                Aggregate::Sum,
+            )),
+            Method::Shifted => count_shifted(depths, size),
+            Method::Parallel => {
+                let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+                count_parallel(depths, size, threads)
+            }
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+#[error("Unknown method: {0}")]
+pub struct UnknownMethod(String);
+
+impl FromStr for Method {
+    type Err = UnknownMethod;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "reference" => Ok(Method::Reference),
+            "shifted" => Ok(Method::Shifted),
+            "parallel" => Ok(Method::Parallel),
+            _ => Err(UnknownMethod(s.to_owned())),
+        }
+    }
+}
+
+/// Count the increases between consecutive sums of `size` depths. Two
+/// neighbouring windows share all but their first and last depths, so the
+/// later sum is larger exactly when `depths[i + size] > depths[i]`.
+fn count_shifted(depths: &[u32], size: usize) -> usize {
+    let later = depths.get(size..).unwrap_or_default();
+    // Summing comparisons rather than filtering keeps the loop branch-free.
+    later
+        .iter()
+        .zip(depths)
+        .map(|(later, earlier)| (later > earlier) as usize)
+        .sum()
+}
+
+/// Fewest comparisons worth handing to another thread.
+const MIN_CHUNK: usize = 1 << 16;
+
+/// `count_shifted`, split across up to `threads` threads. Each chunk of
+/// comparisons also borrows the `size` depths after it, so that comparisons
+/// across chunk boundaries are still made, once.
+fn count_parallel(depths: &[u32], size: usize, threads: usize) -> usize {
+    let comparisons = depths.len().saturating_sub(size);
+    let chunk = comparisons.div_ceil(threads.max(1)).max(MIN_CHUNK);
+    if comparisons <= chunk {
+        return count_shifted(depths, size);
+    }
+
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..comparisons)
+            .step_by(chunk)
+            .map(|start| {
+                let end = (start + chunk).min(comparisons) + size;
+                scope.spawn(move || count_shifted(&depths[start..end], size))
+            })
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().expect("counting thread panicked"))
+            .sum()
+    })
+}
+
+/// A run of consecutive depths, by the 1-based lines they span. Lines skipped
+/// as bad may fall inside a run, so its length is counted separately.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Run {
+    start: usize,
+    end: usize,
+    len: usize,
+}
+
+impl Run {
+    fn new(lineno: usize) -> Run {
+        Run {
+            start: lineno,
+            end: lineno,
+            len: 1,
+        }
+    }
+
+    /// Extend the run to include the depth on line `lineno` if `continues`,
+    /// or start a new run there, keeping `longest` up to date.
+    fn step(&mut self, lineno: usize, continues: bool, longest: &mut Run) {
+        *self = match continues {
+            true => Run {
+                end: lineno,
+                len: self.len + 1,
+                ..*self
+            },
+            false => Run::new(lineno),
+        };
+        if self.len > longest.len {
+            *longest = *self;
+        }
+    }
+}
+
+impl std::fmt::Display for Run {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let noun = if self.len == 1 { "depth" } else { "depths" };
+        write!(f, "{} {}, lines {}-{}", self.len, noun, self.start, self.end)
+    }
+}
+
+/// Statistics gathered in one pass over the depths.
+#[derive(Debug, PartialEq)]
+struct Summary {
+    count: usize,
+    sum: u64,
+    /// Extreme depths and the lines they first occur on.
+    min: (u32, usize),
+    max: (u32, usize),
+    increases: usize,
+    decreases: usize,
+    rising: Run,
+    falling: Run,
+    /// Smallest and largest change between consecutive depths, if there's
+    /// more than one depth.
+    changes: Option<(i64, i64)>,
+}
+
+impl Summary {
+    /// Summarize depths paired with their line numbers.
+    fn new(mut depths: impl Iterator<Item = (usize, u32)>) -> Option<Summary> {
+        let (lineno, first) = depths.next()?;
+        let start = Run::new(lineno);
+        let mut summary = Summary {
+            count: 1,
+            sum: first as u64,
+            min: (first, lineno),
+            max: (first, lineno),
+            increases: 0,
+            decreases: 0,
+            rising: start,
+            falling: start,
+            changes: None,
+        };
+
+        let (mut rising, mut falling) = (start, start);
+        let mut prev = first;
+        for (lineno, depth) in depths {
+            summary.count += 1;
+            summary.sum += depth as u64;
+            if depth < summary.min.0 {
+                summary.min = (depth, lineno);
+            }
+            if depth > summary.max.0 {
+                summary.max = (depth, lineno);
+            }
+
+            let change = depth as i64 - prev as i64;
+            summary.changes = Some(match summary.changes {
+                Some((low, high)) => (low.min(change), high.max(change)),
+                None => (change, change),
+            });
+            summary.increases += (change > 0) as usize;
+            summary.decreases += (change < 0) as usize;
+            rising.step(lineno, change > 0, &mut summary.rising);
+            falling.step(lineno, change < 0, &mut summary.falling);
+            prev = depth;
+        }
+
+        Some(summary)
+    }
+}
+
+const HISTOGRAM_BINS: i64 = 10;
+const HISTOGRAM_WIDTH: usize = 40;
+const SPARKLINE_WIDTH: usize = 60;
+/// Sparkline characters from shallowest to deepest.
+const SPARKLINE_LEVELS: &[u8] = b" .:-=+*#%@";
+
+/// Describe the depths for `--report`. The depths are read twice, once to
+/// find their range and once to bin them, so streamed input stays in
+/// constant memory.
+fn report(depths: &LineValues<u32>) -> ProblemResult<String> {
+    let summary = match depths.process_numbered(|it| Summary::new(it))? {
+        Some(summary) => summary,
+        None => return Ok("No depths".to_owned()),
+    };
+
+    let (low, bin_width, bins) = match summary.changes {
+        Some((low, high)) => {
+            let bin_width = (high - low) / HISTOGRAM_BINS + 1;
+            (low, bin_width, ((high - low) / bin_width + 1) as usize)
+        }
+        None => (0, 1, 0),
+    };
+    let mut histogram = vec![0; bins];
+    let per_column = summary.count.div_ceil(SPARKLINE_WIDTH) as u64;
+    let mut columns = Vec::with_capacity(SPARKLINE_WIDTH);
+    // A streamed file is read again, and may no longer fit the first pass.
+    let changed = || anyhow!("Depths changed while reporting on them");
+    depths.process(|it| {
+        let mut prev = None;
+        let mut column = (0, 0_u64);
+        for depth in it {
+            if depth < summary.min.0 || depth > summary.max.0 {
+                return Err(changed());
+            }
+            if let Some(prev) = prev.replace(depth) {
+                let bin = (depth as i64 - prev as i64 - low).div_euclid(bin_width);
+                let count = usize::try_from(bin)
+                    .ok()
+                    .and_then(|bin| histogram.get_mut(bin))
+                    .ok_or_else(changed)?;
+                *count += 1;
+            }
+            column = (column.0 + depth as u64, column.1 + 1);
+            if column.1 == per_column {
+                columns.push(column.0 / column.1);
+                column = (0, 0);
+            }
+        }
+        columns.extend(column.0.checked_div(column.1));
+        Ok(())
+    })??;
+
+    let mut lines = vec![
+        format!("Depths: {}", summary.count),
+        format!("Min: {} (line {})", summary.min.0, summary.min.1),
+        format!("Max: {} (line {})", summary.max.0, summary.max.1),
+        format!("Mean: {:.2}", summary.sum as f64 / summary.count as f64),
+        format!(
+            "Changes: {} increases, {} decreases, {} unchanged",
+            summary.increases,
+            summary.decreases,
+            summary.count - 1 - summary.increases - summary.decreases
+        ),
+        format!("Longest increasing run: {}", summary.rising),
+        format!("Longest decreasing run: {}", summary.falling),
+    ];
+
+    if !histogram.is_empty() {
+        lines.push("Change histogram:".to_owned());
+        let most = histogram.iter().copied().max().unwrap_or(0).max(1);
+        for (i, &count) in histogram.iter().enumerate() {
+            let from = low + i as i64 * bin_width;
+            let bar = "#".repeat((count * HISTOGRAM_WIDTH).div_ceil(most));
+            let to = from + bin_width - 1;
+            lines.push(format!("  {:>6} to {:>6} | {} {}", from, to, bar, count));
+        }
+    }
+
+    let (min, max) = (summary.min.0 as u64, summary.max.0 as u64);
+    let levels = SPARKLINE_LEVELS.len() as u64 - 1;
+    let sparkline: String = columns
+        .iter()
+        .map(|&depth| {
+            let level = (depth - min) * levels / (max - min).max(1);
+            SPARKLINE_LEVELS[level as usize] as char
+        })
+        .collect();
+    lines.push(format!("Profile: |{}|", sparkline));
+
+    Ok(lines.join("\n"))
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{run_problem, ProblemResult};
-    use crate::utils::parse_lines;
+    use super::{
+        count_increases, count_parallel, count_shifted, parse_filter, part1, part2, Aggregate,
+        Analysis, Comparison, Filter, Method, ProblemResult, RunningCount, Run, Summary,
+    };
+    use crate::diagnostic::Leniency;
+    use crate::input::InputSource;
+    use crate::utils::{parse_lines, parse_lines_from_source, LineValues};
 
     #[test]
     fn test_example() -> ProblemResult<()> {
@@ -181,11 +994,174 @@ mod tests {
 260
 263"#;
         let depths: Vec<u32> = parse_lines(&s[..])?;
-        let (part1, part2) = run_problem(&depths)?;
 
-        assert_eq!(part1, 7);
-        assert_eq!(part2, 5);
+        let analysis = Analysis::default();
+        assert_eq!(part1(depths.iter().cloned(), &analysis), 7);
+        assert_eq!(part2(depths.iter().cloned(), &analysis), 5);
 
         Ok(())
     }
+
+    #[test]
+    fn test_stream() -> ProblemResult<()> {
+        let source = InputSource::Text("199\n200\n208\n210\n200\n207".to_owned());
+        let depths: LineValues<u32> =
+            LineValues::from_source(source, true, &mut Leniency::default())?;
+        let analysis = Analysis::default();
+        assert_eq!(depths.process(|it| part1(it, &analysis))?, 4);
+        assert_eq!(depths.process(|it| part2(it, &analysis))?, 1);
+
+        let source = InputSource::Text("199\n200\nx\n210".to_owned());
+        let depths: LineValues<u32> =
+            LineValues::from_source(source, true, &mut Leniency::default())?;
+        assert!(depths.process(|it| part1(it, &analysis)).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_analysis() {
+        let depths = [199, 200, 208, 210, 200, 207, 240, 269, 260, 263];
+        let count = |window, aggregate, compare, threshold| {
+            let analysis = Analysis {
+                window,
+                aggregate,
+                compare,
+                threshold,
+                ..Analysis::default()
+            };
+            part2(depths.iter().cloned(), &analysis)
+        };
+
+        // Window means and sums rise and fall together.
+        assert_eq!(count(3, Aggregate::Mean, Comparison::Increase, 0.0), 5);
+        assert_eq!(count(3, Aggregate::Sum, Comparison::Decrease, 0.0), 1);
+        assert_eq!(count(3, Aggregate::Sum, Comparison::Unchanged, 0.0), 1);
+        assert_eq!(count(1, Aggregate::Median, Comparison::Increase, 0.0), 7);
+        // Medians of 2 change by 4.5, 5, -4, -1.5, 20, 31, 10, -3
+        assert_eq!(count(2, Aggregate::Median, Comparison::Change, 4.0), 5);
+        assert_eq!(count(4, Aggregate::Min, Comparison::Unchanged, 0.0), 3);
+        assert_eq!(count(2, Aggregate::Max, Comparison::Change, 10.0), 2);
+        // Means of 3 change by 3.67, 0, -0.33, 10, 23, 17.67, 7.67
+        assert_eq!(count(3, Aggregate::Mean, Comparison::Change, 10.0), 2);
+
+        assert_eq!(count_increases("abcba".chars()), 2);
+
+        // Counting one depth at a time agrees with counting a whole trace.
+        let analysis = Analysis {
+            aggregate: Aggregate::Median,
+            ..Analysis::default()
+        };
+        let mut running = RunningCount::new(&analysis, 3);
+        depths.iter().for_each(|&d| running.push(d));
+        assert_eq!(running.count(), part2(depths.iter().cloned(), &analysis));
+    }
+
+    #[test]
+    fn test_filters() {
+        let count = |depths: &[u32], compare, filters: &[&str]| {
+            let analysis = Analysis {
+                compare,
+                filters: filters.iter().map(|f| parse_filter(f).unwrap()).collect(),
+                ..Analysis::default()
+            };
+            part1(depths.iter().cloned(), &analysis)
+        };
+
+        let depths = [199, 200, 208, 210, 200, 207, 240, 269, 260, 263];
+        // A moving average rises and falls with the puzzle's window sums.
+        assert_eq!(count(&depths, Comparison::Increase, &["mean:3"]), 5);
+        // Medians of 3: 200, 208, 208, 207, 207, 240, 260, 263
+        assert_eq!(count(&depths, Comparison::Unchanged, &["median:3"]), 2);
+        assert_eq!(count(&depths, Comparison::Increase, &["ema:1"]), 7);
+        // Smoothing leaves only the dip from 210 to 200.
+        assert_eq!(count(&depths, Comparison::Decrease, &["ema:0.2"]), 1);
+        assert_eq!(count(&depths, Comparison::Decrease, &["mean:2", "ema:0.5"]), 2);
+
+        let spiky = [100, 101, 102, 5, 103, 104];
+        assert_eq!(count(&spiky, Comparison::Decrease, &[]), 1);
+        assert_eq!(count(&spiky, Comparison::Decrease, &["zscore:3:3"]), 0);
+
+        // The running count filters the same way.
+        let analysis = Analysis {
+            filters: vec![Filter::ZScore(3.0, 3)],
+            compare: Comparison::Decrease,
+            ..Analysis::default()
+        };
+        let mut running = RunningCount::new(&analysis, 1);
+        spiky.iter().for_each(|&d| running.push(d));
+        assert_eq!(running.count(), 0);
+
+        assert_eq!(parse_filter("ema:0.5"), Ok(Filter::Ema(0.5)));
+        assert!(parse_filter("ema:2").is_err());
+        assert!(parse_filter("mean:0").is_err());
+        assert!(parse_filter("blur:3").is_err());
+    }
+
+    #[test]
+    fn test_methods() {
+        // A noisy descent, long enough to split across threads.
+        let mut seed = 1u64;
+        let depths: Vec<u32> = (0..300_000u32)
+            .map(|i| {
+                seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                i / 4 + (seed >> 56) as u32
+            })
+            .collect();
+
+        for &size in &[1, 3, 5] {
+            let expected = Method::Reference.count(&depths, size);
+            assert_eq!(count_shifted(&depths, size), expected);
+            for &threads in &[1, 2, 3, 7, 64] {
+                assert_eq!(count_parallel(&depths, size, threads), expected);
+            }
+            assert_eq!(Method::Parallel.count(&depths, size), expected);
+        }
+
+        let analysis = Analysis::default();
+        let example = [199, 200, 208, 210, 200, 207, 240, 269, 260, 263];
+        assert_eq!(count_shifted(&example, 1), part1(example.iter().cloned(), &analysis));
+        assert_eq!(count_shifted(&example, 3), part2(example.iter().cloned(), &analysis));
+        assert_eq!(count_shifted(&example[..2], 3), 0);
+        assert_eq!(count_parallel(&[], 3, 4), 0);
+    }
+
+    #[test]
+    fn test_summary() {
+        let depths = [199, 200, 208, 210, 200, 207, 240, 269, 260, 263];
+        let summary = Summary::new(depths.iter().cloned().zip(1..).map(|(d, l)| (l, d))).unwrap();
+        assert_eq!(summary.min, (199, 1));
+        assert_eq!(summary.max, (269, 8));
+        assert_eq!((summary.increases, summary.decreases), (7, 2));
+        // The first of two equally long runs wins.
+        assert_eq!(summary.rising, Run { start: 1, end: 4, len: 4 });
+        assert_eq!(summary.falling, Run { start: 4, end: 5, len: 2 });
+        assert_eq!(summary.changes, Some((-10, 33)));
+        assert_eq!(summary.rising.to_string(), "4 depths, lines 1-4");
+
+        assert_eq!(Summary::new(std::iter::empty()), None);
+    }
+
+    #[test]
+    fn test_lenient() {
+        let source = InputSource::Text("199\nx\n208\n2y0".to_owned());
+
+        let err = parse_lines_from_source::<u32>(&source, &mut Leniency::new(false)).unwrap_err();
+        assert!(err.to_string().starts_with("2 lines failed to parse"));
+
+        let mut leniency = Leniency::new(true);
+        let depths: Vec<u32> = parse_lines_from_source(&source, &mut leniency).unwrap();
+        assert_eq!(depths, vec![199, 208]);
+        let warnings = leniency.into_warnings();
+        assert_eq!(warnings.iter().map(|w| w.lineno).collect::<Vec<_>>(), vec![2, 4]);
+
+        // Reports give the lines depths came from, not their positions.
+        let source = InputSource::Text("5\nx\n1\n2\n\n3\n9".to_owned());
+        let depths: LineValues<u32> =
+            LineValues::ints_from_source(source, false, &mut Leniency::new(true)).unwrap();
+        let summary = depths.process_numbered(|it| Summary::new(it)).unwrap().unwrap();
+        assert_eq!(summary.min, (1, 3));
+        assert_eq!(summary.max, (9, 7));
+        assert_eq!(summary.rising.to_string(), "4 depths, lines 3-7");
+    }
 }
@@ -66,93 +66,81 @@
 /// up 3 decreases your aim by 3, resulting in a value of 2.
 /// down 8 adds 8 to your aim, resulting in a value of 10.
 /// forward 2 adds 2 to your horizontal position, a total of 15. Because your aim is 10, your depth increases by 2*10=20 to a total of 60.
-
+///
 /// After following these new instructions, you would have a horizontal
 /// position of 15 and a depth of 60. (Multiplying these produces 900.)
-
+///
 /// Using this new interpretation of the commands, calculate the horizontal
 /// position and depth you would have after following the planned course. What
 /// do you get if you multiply your final horizontal position by your final
 /// depth?
+use aoc_derive::PuzzleParse;
 use structopt::StructOpt;
-use thiserror::Error;
 
-use crate::utils::{parse_lines_from_path, ProblemResult};
+use crate::diagnostic::Leniency;
+use crate::input::InputOpt;
+use crate::solver::Solver;
+use crate::utils::{LineValues, ProblemResult};
 
 #[derive(StructOpt, Debug)]
 pub struct P2 {
-    #[structopt(
-        short = "i",
-        long = "input",
-        default_value = "inputs/problem2/input.txt"
-    )]
-    input: String,
+    #[structopt(flatten)]
+    input: InputOpt,
+
+    /// Read commands lazily while solving instead of loading them up front.
+    #[structopt(long = "stream")]
+    stream: bool,
 }
 
-impl P2 {
-    pub fn run(&self) -> ProblemResult {
-        let commands: Vec<Command> = parse_lines_from_path(&self.input)?;
+impl Solver for P2 {
+    const DAY: u32 = 2;
+    const TITLE: &'static str = "Dive!";
 
-        let (p1, p2) = run_problem(commands)?;
-        println!("Part 1: {}", p1);
-        println!("Part 2: {}", p2);
+    type Input = LineValues<Command>;
+    type Part1 = u64;
+    type Part2 = u64;
 
-        Ok(())
+    fn input(&self) -> &InputOpt {
+        &self.input
     }
-}
 
-fn run_problem(cmds: Vec<Command>) -> ProblemResult<(u64, u64)> {
-    let mut sub1 = Submarine::new();
-    sub1.apply_all_part1(cmds.iter().cloned());
-    let part1 = sub1.depth * sub1.horizontal_pos;
+    fn input_mut(&mut self) -> &mut InputOpt {
+        &mut self.input
+    }
 
-    let mut sub2 = Submarine::new();
-    sub2.apply_all(cmds.iter().cloned());
-    let part2 = sub2.depth * sub2.horizontal_pos;
+    fn parse(&self, leniency: &mut Leniency) -> ProblemResult<LineValues<Command>> {
+        LineValues::from_source(self.source(), self.stream, leniency)
+    }
 
-    Ok((part1, part2))
-}
+    fn part1(&self, cmds: &LineValues<Command>) -> ProblemResult<u64> {
+        cmds.process(|it| part1(it))
+    }
 
-#[derive(Clone, Copy, Debug)]
-enum Command {
-    Forward(u64),
-    Up(u64),
-    Down(u64),
+    fn part2(&self, cmds: &LineValues<Command>) -> ProblemResult<u64> {
+        cmds.process(|it| part2(it))
+    }
 }
 
-#[derive(Error, Debug)]
-pub enum CommandParseError {
-    #[error("Expected two words in command: got {0}")]
-    WrongWordCount(usize),
-
-    #[error("Failed to parse command magnitude")]
-    ParseError(#[from] std::num::ParseIntError),
-
-    #[error("Invalid command verb: {0}")]
-    InvalidVerb(String),
+fn part1(cmds: impl Iterator<Item = Command>) -> u64 {
+    let mut sub = Submarine::new();
+    sub.apply_all_part1(cmds);
+    sub.depth * sub.horizontal_pos
 }
 
-impl std::str::FromStr for Command {
-    type Err = CommandParseError;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let parts: Vec<&str> = s.split(" ").collect();
-        if parts.len() != 2 {
-            return Err(CommandParseError::WrongWordCount(parts.len()));
-        }
-
-        let verb = parts[0];
-        let mag = u64::from_str(parts[1])?;
-
-        let parsed = match verb {
-            "forward" => Command::Forward(mag),
-            "up" => Command::Up(mag),
-            "down" => Command::Down(mag),
-            _ => return Err(CommandParseError::InvalidVerb(verb.to_owned())),
-        };
+fn part2(cmds: impl Iterator<Item = Command>) -> u64 {
+    let mut sub = Submarine::new();
+    sub.apply_all(cmds);
+    sub.depth * sub.horizontal_pos
+}
 
-        Ok(parsed)
-    }
+#[derive(PuzzleParse, Clone, Copy, Debug)]
+pub enum Command {
+    #[pattern("forward {0}")]
+    Forward(u64),
+    #[pattern("up {0}")]
+    Up(u64),
+    #[pattern("down {0}")]
+    Down(u64),
 }
 
 struct Submarine {
@@ -210,7 +198,7 @@ impl Submarine {
 
 #[cfg(test)]
 mod tests {
-    use super::{run_problem, Command, ProblemResult};
+    use super::{part1, part2, Command, ProblemResult};
     use crate::utils::parse_lines;
 
     #[test]
@@ -222,9 +210,8 @@ up 3
 down 8
 forward 2"#;
         let commands: Vec<Command> = parse_lines(&s[..])?;
-        let (p1, p2) = run_problem(commands)?;
-        assert_eq!(p1, 150);
-        assert_eq!(p2, 900);
+        assert_eq!(part1(commands.iter().cloned()), 150);
+        assert_eq!(part2(commands.iter().cloned()), 900);
 
         Ok(())
     }
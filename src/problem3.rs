@@ -116,47 +116,68 @@
 /// is the life support rating of the submarine? (Be sure to represent your
 /// answer in decimal, not binary.)
 use std::convert::TryInto;
+use std::ops::Range;
+
 use structopt::StructOpt;
 use thiserror::Error;
 
-use crate::utils::{parse_lines_from_path, ProblemResult};
+use crate::diagnostic::{Leniency, Spanned};
+use crate::input::InputOpt;
+use crate::solver::Solver;
+use crate::utils::{parse_lines_from_source, ProblemResult};
 
 #[derive(StructOpt, Debug)]
 pub struct P3 {
-    #[structopt(
-        short = "i",
-        long = "input",
-        default_value = "inputs/problem3/input.txt"
-    )]
-    input: String,
+    #[structopt(flatten)]
+    input: InputOpt,
 }
 
-impl P3 {
-    pub fn run(&self) -> ProblemResult {
-        let nums: Vec<BinaryInt<12>> = parse_lines_from_path(&self.input)?;
+impl Solver for P3 {
+    const DAY: u32 = 3;
+    const TITLE: &'static str = "Binary Diagnostic";
 
-        let (p1, p2) = run_problem(nums)?;
+    type Input = Vec<BinaryInt<12>>;
+    type Part1 = u64;
+    type Part2 = u64;
 
-        println!("Part 1: {}", p1);
-        println!("Part 2: {}", p2);
+    fn input(&self) -> &InputOpt {
+        &self.input
+    }
 
-        Ok(())
+    fn input_mut(&mut self) -> &mut InputOpt {
+        &mut self.input
+    }
+
+    fn parse(&self, leniency: &mut Leniency) -> ProblemResult<Vec<BinaryInt<12>>> {
+        Ok(parse_lines_from_source(&self.source(), leniency)?)
+    }
+
+    fn part1(&self, nums: &Vec<BinaryInt<12>>) -> ProblemResult<u64> {
+        Ok(part1(nums))
+    }
+
+    fn part2(&self, nums: &Vec<BinaryInt<12>>) -> ProblemResult<u64> {
+        part2(nums)
     }
 }
 
-fn run_problem<const N: usize>(nums: Vec<BinaryInt<N>>) -> ProblemResult<(u64, u64)> {
-    let (gamma, epsilon) = compute_rates(&nums);
+fn part1<const N: usize>(nums: &[BinaryInt<N>]) -> u64 {
+    let (gamma, epsilon) = compute_rates(nums);
     let gamma: u64 = gamma.into();
     let epsilon: u64 = epsilon.into();
 
-    let oxygen_rating: u64 = compute_rating(&nums, Rating::Oxygen)?.into();
-    let co2_rating: u64 = compute_rating(&nums, Rating::CO2)?.into();
+    gamma * epsilon
+}
+
+fn part2<const N: usize>(nums: &[BinaryInt<N>]) -> ProblemResult<u64> {
+    let oxygen_rating = compute_rating(nums, Rating::Oxygen)?;
+    let co2_rating = compute_rating(nums, Rating::CO2)?;
 
-    Ok((gamma * epsilon, oxygen_rating * co2_rating))
+    Ok(oxygen_rating * co2_rating)
 }
 
 #[derive(Debug, Clone, Copy)]
-struct BinaryInt<const N: usize>([bool; N]);
+pub struct BinaryInt<const N: usize>([bool; N]);
 
 impl<const N: usize> BinaryInt<N> {
     fn at(&self, i: usize) -> bool {
@@ -177,11 +198,11 @@ impl<const N: usize> std::str::FromStr for BinaryInt<N> {
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let parsed_bits: Vec<bool> = s
-            .chars()
-            .map(|c| match c {
+            .char_indices()
+            .map(|(col, c)| match c {
                 '0' => Ok(false),
                 '1' => Ok(true),
-                _ => Err(IntParseError::InvalidBit(c)),
+                _ => Err(IntParseError::InvalidBit { bit: c, col }),
             })
             .collect::<Result<_, _>>()?;
 
@@ -211,17 +232,30 @@ impl<const N: usize> From<BinaryInt<N>> for u64 {
 
 #[derive(Error, Debug)]
 pub enum IntParseError {
-    #[error("Invalid bit: {0:?}")]
-    InvalidBit(char),
+    #[error("Invalid bit: {bit:?}")]
+    InvalidBit { bit: char, col: usize },
     #[error("Wrong number of bits. Expected {expected}. Got {actual}.")]
     WrongBitCount { expected: usize, actual: usize },
 }
 
-fn compute_rates<const N: usize>(nums: &Vec<BinaryInt<N>>) -> (BinaryInt<N>, BinaryInt<N>) {
+impl Spanned for IntParseError {
+    fn span(&self) -> Option<Range<usize>> {
+        match self {
+            IntParseError::InvalidBit { bit, col } => Some(*col..col + bit.len_utf8()),
+            // Point at the extra bits, if there are any.
+            IntParseError::WrongBitCount { expected, actual } if actual > expected => {
+                Some(*expected..*actual)
+            }
+            IntParseError::WrongBitCount { .. } => None,
+        }
+    }
+}
+
+fn compute_rates<const N: usize>(nums: &[BinaryInt<N>]) -> (BinaryInt<N>, BinaryInt<N>) {
     let gamma = {
         let mut bits = [false; N];
-        for i in 0..N {
-            bits[i] = most_common_bit(nums.iter().map(|num| num.at(i)));
+        for (i, bit) in bits.iter_mut().enumerate() {
+            *bit = most_common_bit(nums.iter().map(|num| num.at(i)));
         }
         BinaryInt(bits)
     };
@@ -242,8 +276,8 @@ pub enum InvalidInput {
     NonUnique,
 }
 
-fn compute_rating<const N: usize>(nums: &Vec<BinaryInt<N>>, rating: Rating) -> ProblemResult<u64> {
-    let mut nums: Vec<BinaryInt<N>> = nums.clone();
+fn compute_rating<const N: usize>(nums: &[BinaryInt<N>], rating: Rating) -> ProblemResult<u64> {
+    let mut nums: Vec<BinaryInt<N>> = nums.to_vec();
     for i in 0..N {
         let most_common = most_common_bit(nums.iter().map(|n| n.at(i)));
         let filter_bit = if rating == Rating::Oxygen {
@@ -255,12 +289,12 @@ fn compute_rating<const N: usize>(nums: &Vec<BinaryInt<N>>, rating: Rating) -> P
 
         if nums.len() == 1 {
             return Ok(nums[0].into());
-        } else if nums.len() == 0 {
-            return Err(InvalidInput::NonUnique)?;
+        } else if nums.is_empty() {
+            return Err(InvalidInput::NonUnique.into());
         }
     }
 
-    return Err(InvalidInput::NonUnique)?;
+    Err(InvalidInput::NonUnique.into())
 }
 
 struct BitCounts {
@@ -279,11 +313,7 @@ impl BitCounts {
 
 fn most_common_bit(it: impl Iterator<Item = bool>) -> bool {
     let counts = count_values(it);
-    if counts.trues >= counts.falses {
-        true
-    } else {
-        false
-    }
+    counts.trues >= counts.falses
 }
 
 fn count_values(it: impl Iterator<Item = bool>) -> BitCounts {
@@ -300,7 +330,7 @@ fn count_values(it: impl Iterator<Item = bool>) -> BitCounts {
 
 #[cfg(test)]
 mod tests {
-    use super::{run_problem, BinaryInt, ProblemResult};
+    use super::{part1, part2, BinaryInt, ProblemResult};
     use crate::utils::parse_lines;
 
     #[test]
@@ -318,9 +348,8 @@ mod tests {
 00010
 01010"#;
         let nums: Vec<BinaryInt<5>> = parse_lines(&s[..])?;
-        let (p1, p2) = run_problem(nums)?;
-        assert_eq!(p1, 198);
-        assert_eq!(p2, 230);
+        assert_eq!(part1(&nums), 198);
+        assert_eq!(part2(&nums)?, 230);
         Ok(())
     }
 }
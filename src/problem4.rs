@@ -75,33 +75,63 @@
 ///
 /// To guarantee victory against the giant squid, figure out which board will
 /// win first. What will your final score be if you choose that board?
+use std::fmt::Display;
+use std::ops::Range;
 use std::str::FromStr;
 
+use anyhow::anyhow;
 use structopt::StructOpt;
 use thiserror::Error;
 
-use crate::utils::ProblemResult;
+use crate::diagnostic::{Leniency, Spanned};
+use crate::input::InputOpt;
+use crate::solver::Solver;
+use crate::utils::combinator::{integer, literal, map, parse_all, separated, SyntaxError};
+use crate::utils::grid::{Grid, GridParseError};
+use crate::utils::{parse_blocks, read_text, Blocks, ProblemResult};
 
 #[derive(StructOpt, Debug)]
 pub struct P4 {
-    #[structopt(
-        short = "i",
-        long = "input",
-        default_value = "inputs/problem4/input.txt"
-    )]
-    input: String,
+    #[structopt(flatten)]
+    input: InputOpt,
 }
 
-impl P4 {
-    pub fn run(&self) -> ProblemResult {
-        let input = std::fs::read_to_string(&self.input)?;
-        let (p1, p2) = run_problem(&input)?;
+impl Solver for P4 {
+    const DAY: u32 = 4;
+    const TITLE: &'static str = "Giant Squid";
 
-        println!("Part 1: {}", p1);
-        println!("Part 2: {}", p2);
+    type Input = Game;
+    type Part1 = u64;
+    type Part2 = u64;
 
-        Ok(())
+    fn input(&self) -> &InputOpt {
+        &self.input
+    }
+
+    fn input_mut(&mut self) -> &mut InputOpt {
+        &mut self.input
+    }
+
+    fn parse(&self, leniency: &mut Leniency) -> ProblemResult<Game> {
+        let source = self.source();
+        let input = read_text(&source, leniency)?;
+        Game::parse(&input, &source, leniency)
+    }
+
+    fn part1(&self, game: &Game) -> ProblemResult<u64> {
+        part1(game)
     }
+
+    fn part2(&self, game: &Game) -> ProblemResult<u64> {
+        part2(game)
+    }
+}
+
+/// The numbers to draw and the boards to play them on.
+#[derive(Debug)]
+pub struct Game {
+    draws: Vec<u8>,
+    boards: Vec<Board>,
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -112,8 +142,7 @@ struct BoardCell {
 
 #[derive(Clone, Debug)]
 struct Board {
-    /// Indexed as row, col.
-    state: [[BoardCell; 5]; 5],
+    state: Grid<BoardCell>,
 }
 
 struct Bingo {
@@ -126,29 +155,16 @@ struct Bingo {
 pub struct NoBingo {}
 
 impl Board {
-    fn new(nums: [[u8; 5]; 5]) -> Board {
-        let init_cell = BoardCell {
-            num: 0,
-            drawn: false,
-        };
-        let mut state = [[init_cell; 5]; 5];
-
-        for row in 0..5 {
-            for col in 0..5 {
-                state[row][col] = BoardCell {
-                    num: nums[row][col],
-                    drawn: false,
-                };
-            }
-        }
+    fn new(nums: &Grid<u8>) -> Board {
+        let state = nums.map(|&num| BoardCell { num, drawn: false });
         Board { state }
     }
 
     fn iter_cells(&self) -> impl Iterator<Item = &BoardCell> {
-        self.state.iter().map(|row| row.iter()).flatten()
+        self.state.cells()
     }
 
-    fn simulate(mut self, draws: &Vec<u8>) -> Result<(Board, Bingo), NoBingo> {
+    fn simulate(mut self, draws: &[u8]) -> Result<(Board, Bingo), NoBingo> {
         for (turn, &draw) in draws.iter().enumerate() {
             if self.apply_draw(draw) {
                 return Ok((self, Bingo { turn, draw }));
@@ -173,23 +189,11 @@ impl Board {
     }
 
     fn apply_draw(&mut self, num: u8) -> bool {
-        let mut found_loc: Option<(usize, usize)> = None;
-
-        'outer: for row in 0..5 {
-            for col in 0..5 {
-                let cell = &mut self.state[row][col];
-                if cell.num == num {
-                    cell.drawn = true;
-                    found_loc = Some((row, col));
-                    break 'outer;
-                }
-            }
-        }
-
-        match found_loc {
+        match self.state.position(|cell| cell.num == num) {
             Some((row, col)) => {
-                let row_bingo = (0..5).map(|i| self.state[row][i].drawn).all(|x| x);
-                let col_bingo = (0..5).map(|i| self.state[i][col].drawn).all(|x| x);
+                self.state[(row, col)].drawn = true;
+                let row_bingo = self.state.row(row).iter().all(|cell| cell.drawn);
+                let col_bingo = self.state.column(col).all(|cell| cell.drawn);
                 row_bingo || col_bingo
             }
             None => false,
@@ -197,32 +201,21 @@ impl Board {
     }
 }
 
-fn run_problem(input: &str) -> ProblemResult<(u64, u64)> {
-    let parts: Vec<String> = input.split("\n\n").map(|s| s.to_owned()).collect();
-    let first_line: &str = parts.first().ok_or(ParseProblemError::EmptyInput)?;
-
-    let draws: Vec<u8> = first_line
-        .split(",")
-        .map(|n| n.parse())
-        .collect::<Result<_, _>>()
-        .map_err(|e| ParseProblemError::DrawNum(e))?;
-
-    let boards: Vec<Board> = parts[1..]
-        .iter()
-        .enumerate()
-        .map(|(i, s)| {
-            Board::from_str(s).map_err(|e| ParseProblemError::BoardParse { n: i, source: e })
-        })
-        .collect::<Result<_, _>>()?;
-
-    let boards = simulate_all(boards, &draws)?;
+fn part1(game: &Game) -> ProblemResult<u64> {
+    let boards = simulate_all(game.boards.clone(), &game.draws)?;
     let (winner, winner_bingo) = &boards[0];
+
+    Ok(winner.score(winner_bingo))
+}
+
+fn part2(game: &Game) -> ProblemResult<u64> {
+    let boards = simulate_all(game.boards.clone(), &game.draws)?;
     let (loser, loser_bingo) = &boards[boards.len() - 1];
 
-    Ok((winner.score(&winner_bingo), loser.score(&loser_bingo)))
+    Ok(loser.score(loser_bingo))
 }
 
-fn simulate_all(boards: Vec<Board>, draws: &Vec<u8>) -> ProblemResult<Vec<(Board, Bingo)>> {
+fn simulate_all(boards: Vec<Board>, draws: &[u8]) -> ProblemResult<Vec<(Board, Bingo)>> {
     let mut results: Vec<(Board, Bingo)> = boards
         .into_iter()
         .map(|board| board.simulate(draws))
@@ -233,68 +226,80 @@ fn simulate_all(boards: Vec<Board>, draws: &Vec<u8>) -> ProblemResult<Vec<(Board
     Ok(results)
 }
 
-#[derive(Error, Debug)]
-pub enum ParseProblemError {
-    #[error("Empty input")]
-    EmptyInput,
+/// The numbers to draw, from the first line of the input.
+struct Draws(Vec<u8>);
 
-    #[error("Failed to parse number draw.")]
-    DrawNum(std::num::ParseIntError),
+impl FromStr for Draws {
+    type Err = SyntaxError;
 
-    #[error("Failed to parse board {n}.")]
-    BoardParse { n: usize, source: ParseBoardError },
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_all(&map(separated(integer(), literal(",")), Draws), s)
+    }
 }
 
 #[derive(Error, Debug)]
 pub enum ParseBoardError {
-    #[error("Expected 5 rows, got {0}")]
-    BadRowCount(usize),
-
-    #[error("Expected row {row} to contain 5 entries, got {len}")]
-    BadRowSize { row: usize, len: usize },
-
-    #[error("Failed to parse number: line={row} row={col}")]
-    ParseInt {
-        row: usize,
-        col: usize,
-        source: std::num::ParseIntError,
-    },
+    #[error(transparent)]
+    Grid(#[from] GridParseError<std::num::ParseIntError>),
+
+    #[error("Expected a 5x5 board, got {width}x{height}")]
+    WrongSize { width: usize, height: usize },
 }
 
-impl FromStr for Board {
-    type Err = ParseBoardError;
+impl Spanned for ParseBoardError {
+    fn span(&self) -> Option<Range<usize>> {
+        match self {
+            ParseBoardError::Grid(e) => e.span(),
+            ParseBoardError::WrongSize { .. } => None,
+        }
+    }
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let lines: Vec<&str> = s.lines().collect();
-        if lines.len() != 5 {
-            return Err(ParseBoardError::BadRowCount(lines.len()));
+    fn line(&self) -> usize {
+        match self {
+            ParseBoardError::Grid(e) => e.line(),
+            ParseBoardError::WrongSize { .. } => 0,
         }
+    }
+}
 
-        let mut nums: [[u8; 5]; 5] = [[0; 5]; 5];
+impl Game {
+    /// Parse the draws and boards, reporting every bad board at once, or
+    /// skipping them if `leniency` allows.
+    fn parse(input: &str, name: impl Display, leniency: &mut Leniency) -> ProblemResult<Game> {
+        let Blocks { header, blocks } = parse_blocks::<Draws, Board>(input, name, leniency)?;
+        // Skipping bad boards can leave none to play.
+        if blocks.is_empty() {
+            return Err(anyhow!("No boards"));
+        }
 
-        for (row, line) in lines.iter().enumerate() {
-            let parts: Vec<&str> = line.split_whitespace().collect::<Vec<&str>>();
-            if parts.len() != 5 {
-                return Err(ParseBoardError::BadRowSize {
-                    row,
-                    len: parts.len(),
-                });
-            }
+        Ok(Game {
+            draws: header.0,
+            boards: blocks.into_iter().map(|(_, board)| board).collect(),
+        })
+    }
+}
 
-            for (col, num) in parts.iter().enumerate() {
-                nums[row][col] = match u8::from_str(num) {
-                    Ok(n) => n,
-                    Err(source) => return Err(ParseBoardError::ParseInt { row, col, source }),
-                }
-            }
+impl FromStr for Board {
+    type Err = ParseBoardError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let nums: Grid<u8> = Grid::parse_whitespace(s)?;
+        if (nums.width(), nums.height()) != (5, 5) {
+            return Err(ParseBoardError::WrongSize {
+                width: nums.width(),
+                height: nums.height(),
+            });
         }
-        Ok(Board::new(nums))
+
+        Ok(Board::new(&nums))
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{run_problem, ProblemResult};
+    use super::{part1, Game, ProblemResult};
+    use crate::diagnostic::Leniency;
+
     #[test]
     fn test_example() -> ProblemResult<()> {
         let s = r#"7,4,9,5,11,17,23,2,0,14,21,24,10,16,13,6,15,25,12,22,18,20,8,19,3,26,1
@@ -317,9 +322,31 @@ mod tests {
 22 11 13  6  5
  2  0 12  3  7
 "#;
-        let (p1, _p2) = run_problem(&s)?;
+        let game = Game::parse(s, "<input>", &mut Leniency::default())?;
+
+        assert_eq!(part1(&game)?, 4512);
 
-        assert_eq!(p1, 4512);
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_no_boards() {
+        // Skipping the only board, which is too small, leaves none to play.
+        let err = Game::parse("1,2\n\n1 2 3\n", "<input>", &mut Leniency::new(true));
+        assert_eq!(err.err().map(|e| e.to_string()), Some("No boards".to_owned()));
+    }
+
+    #[test]
+    fn test_parse_crlf_blocks() -> ProblemResult<()> {
+        let s = "1,2,3,4,5,26,27,28,29,30\r\n\r\n\r\n\
+                 1 2 3 4 5\r\n6 7 8 9 10\r\n11 12 13 14 15\r\n16 17 18 19 20\r\n\
+                 21 22 23 24 25\r\n\r\n\r\n\
+                 26 27 28 29 30\r\n31 32 33 34 35\r\n36 37 38 39 40\r\n41 42 43 44 45\r\n\
+                 46 47 48 49 50\r\n\r\n";
+        let game = Game::parse(s, "<input>", &mut Leniency::default())?;
+        assert_eq!(game.boards.len(), 2);
+        // The first row of the first board wins, leaving 325 - 15 unmarked.
+        assert_eq!(part1(&game)?, 310 * 5);
 
         Ok(())
     }
@@ -88,48 +88,63 @@
 ///
 /// Consider all of the lines. At how many points do at least two lines
 /// overlap?
-use std::str::FromStr;
-
+use aoc_derive::PuzzleParse;
 use structopt::StructOpt;
-use thiserror::Error;
 
-use crate::utils::{parse_lines_from_path, ProblemResult};
+use crate::diagnostic::Leniency;
+use crate::input::InputOpt;
+use crate::solver::Solver;
+use crate::utils::{parse_lines_from_source, ProblemResult};
 
 #[derive(StructOpt, Debug)]
 pub struct P5 {
-    #[structopt(
-        short = "i",
-        long = "input",
-        default_value = "inputs/problem5/input.txt"
-    )]
-    input: String,
+    #[structopt(flatten)]
+    input: InputOpt,
 }
 
-impl P5 {
-    pub fn run(&self) -> ProblemResult {
-        let lines: Vec<Line> = parse_lines_from_path(&self.input)?;
-        let (p1, p2) = run_problem(&lines)?;
+impl Solver for P5 {
+    const DAY: u32 = 5;
+    const TITLE: &'static str = "Hydrothermal Venture";
 
-        println!("Part 1: {}", p1);
-        println!("Part 2: {}", p2);
+    type Input = Vec<Line>;
+    type Part1 = usize;
+    type Part2 = usize;
 
-        Ok(())
+    fn input(&self) -> &InputOpt {
+        &self.input
+    }
+
+    fn input_mut(&mut self) -> &mut InputOpt {
+        &mut self.input
+    }
+
+    fn parse(&self, leniency: &mut Leniency) -> ProblemResult<Vec<Line>> {
+        Ok(parse_lines_from_source(&self.source(), leniency)?)
+    }
+
+    fn part1(&self, lines: &Vec<Line>) -> ProblemResult<usize> {
+        Ok(part1(lines))
+    }
+
+    fn part2(&self, lines: &Vec<Line>) -> ProblemResult<usize> {
+        Ok(part2(lines))
     }
 }
 
-fn run_problem(lines: &Vec<Line>) -> ProblemResult<(usize, usize)> {
+fn part1(lines: &[Line]) -> usize {
     let rect_lines: Vec<_> = lines
         .iter()
         .filter(|line| line.is_horizontal_or_vertical())
         .cloned()
         .collect();
-    let p1 = count_overlaps(&rect_lines);
-    let p2 = count_overlaps(lines);
+    count_overlaps(&rect_lines)
+}
 
-    Ok((p1, p2))
+fn part2(lines: &[Line]) -> usize {
+    count_overlaps(lines)
 }
 
-fn count_overlaps(lines: &Vec<Line>) -> usize {
+fn count_overlaps(lines: &[Line]) -> usize {
     let mut locs = std::collections::HashMap::<(i16, i16), u64>::new();
     for line in lines {
         for point in line.iter_points() {
@@ -140,8 +155,9 @@ fn count_overlaps(lines: &Vec<Line>) -> usize {
     locs.iter().filter(|&(_, &v)| v > 1).count()
 }
 
-#[derive(Debug, Clone, Copy)]
-struct Line {
+#[derive(PuzzleParse, Debug, Clone, Copy)]
+#[pattern("{x1},{y1} -> {x2},{y2}")]
+pub struct Line {
     x1: i16,
     y1: i16,
     x2: i16,
@@ -171,60 +187,13 @@ impl Line {
         let x1 = self.x1;
         let y1 = self.y1;
 
-        (0..count).map(move |dist| {
-            (x1 + dist * dx, y1 + dist * dy)
-        })
-    }
-}
-
-#[derive(Error, Debug)]
-pub enum LineParseError {
-    #[error("Invalid line format: {0}")]
-    InvalidFormat(String),
-
-    #[error("Failed to parse int pair: {0}")]
-    InvalidIntPair(String),
-
-    #[error("Failed to parse coordinate")]
-    InvalidInt(#[from] std::num::ParseIntError),
-}
-
-impl FromStr for Line {
-    type Err = LineParseError;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let (coord1, coord2) =
-            split_exactly_once(s, " -> ").ok_or(LineParseError::InvalidFormat(s.to_owned()))?;
-
-        let (x1, y1) = parse_pair::<i16>(coord1)?;
-        let (x2, y2) = parse_pair::<i16>(coord2)?;
-
-        Ok(Line { x1, y1, x2, y2 })
-    }
-}
-
-fn parse_pair<T: FromStr>(s: &str) -> Result<(T, T), LineParseError>
-where
-    LineParseError: std::convert::From<<T as std::str::FromStr>::Err>,
-{
-    let (a, b) = split_exactly_once(s, ",").ok_or(LineParseError::InvalidIntPair(s.to_owned()))?;
-    let a = a.parse::<T>()?;
-    let b = b.parse::<T>()?;
-
-    Ok((a, b))
-}
-
-fn split_exactly_once<'a>(s: &'a str, pat: &str) -> Option<(&'a str, &'a str)> {
-    let mut parts = s.split(pat);
-    match (parts.next(), parts.next()) {
-        (Some(p1), Some(p2)) => Some((p1, p2)),
-        _ => None,
+        (0..count).map(move |dist| (x1 + dist * dx, y1 + dist * dy))
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{run_problem, Line, ProblemResult};
+    use super::{part1, part2, Line, ProblemResult};
     use crate::utils::parse_lines;
 
     #[test]
@@ -240,10 +209,9 @@ mod tests {
 0,0 -> 8,8
 5,5 -> 8,2"#;
         let lines: Vec<Line> = parse_lines(&s[..])?;
-        let (p1, p2) = run_problem(&lines)?;
 
-        assert_eq!(p1, 5);
-        assert_eq!(p2, 12);
+        assert_eq!(part1(&lines), 5);
+        assert_eq!(part2(&lines), 12);
 
         Ok(())
     }
@@ -0,0 +1,451 @@
+use std::fmt::{Display, Write};
+use std::str::FromStr;
+use std::time::Duration;
+
+use serde::Serialize;
+use thiserror::Error;
+
+use crate::diagnostic::Diagnostic;
+use crate::solver::Parts;
+use crate::utils::ProblemResult;
+
+/// The answer to one part of a problem, and how long it took to compute.
+#[derive(Debug)]
+pub struct PartResult {
+    pub answer: ProblemResult<String>,
+    pub time: Duration,
+}
+
+impl PartResult {
+    pub fn new<T: Display>(answer: ProblemResult<T>, time: Duration) -> PartResult {
+        PartResult {
+            answer: answer.map(|a| a.to_string()),
+            time,
+        }
+    }
+}
+
+/// Answers and timings produced by solving a single problem. Parts that
+/// weren't selected to run are `None`.
+#[derive(Debug)]
+pub struct Solution {
+    pub parse_time: Duration,
+    pub part1: Option<PartResult>,
+    pub part2: Option<PartResult>,
+    /// Extra text from the solver, shown in text output only.
+    pub details: Option<String>,
+    /// Input skipped by a lenient parse.
+    pub warnings: Vec<Diagnostic>,
+}
+
+impl Solution {
+    /// The result of a part, if it was run.
+    pub fn part(&self, part: u8) -> Option<&PartResult> {
+        match part {
+            1 => self.part1.as_ref(),
+            2 => self.part2.as_ref(),
+            _ => None,
+        }
+    }
+
+    /// Each part that was run, paired with its part number.
+    pub fn parts(&self) -> Vec<(u8, &PartResult)> {
+        (1..=2)
+            .filter_map(|part| self.part(part).map(|r| (part, r)))
+            .collect()
+    }
+
+    pub fn print(&self) {
+        for (part, result) in self.parts() {
+            match &result.answer {
+                Ok(answer) => println!("Part {}: {}", part, answer),
+                Err(e) => println!("Part {}: error: {:#}", part, e),
+            }
+        }
+
+        let mut timings = vec![format!("parse {}", format_duration(self.parse_time))];
+        for (part, result) in self.parts() {
+            timings.push(format!("part {} {}", part, format_duration(result.time)));
+        }
+        println!("Time: {}", timings.join(", "));
+
+        if let Some(details) = &self.details {
+            println!("\n{}", details);
+        }
+    }
+}
+
+/// The result of running a single day. `solution` is an error if the day's
+/// input could not be loaded or parsed.
+#[derive(Debug)]
+pub struct Outcome {
+    pub day: u32,
+    pub parts: Parts,
+    pub solution: ProblemResult<Solution>,
+}
+
+impl Outcome {
+    /// Whether parsing or either part failed.
+    pub fn failed(&self) -> bool {
+        match &self.solution {
+            Ok(s) => s.parts().iter().any(|(_, r)| r.answer.is_err()),
+            Err(_) => true,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    Text,
+    Json,
+    Csv,
+}
+
+impl Format {
+    pub const VARIANTS: [&'static str; 3] = ["text", "json", "csv"];
+}
+
+#[derive(Error, Debug)]
+#[error("Unknown output format: {0}")]
+pub struct UnknownFormat(String);
+
+impl FromStr for Format {
+    type Err = UnknownFormat;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(Format::Text),
+            "json" => Ok(Format::Json),
+            "csv" => Ok(Format::Csv),
+            _ => Err(UnknownFormat(s.to_owned())),
+        }
+    }
+}
+
+/// A row of machine-readable output.
+pub trait Record: Serialize {
+    /// Comma-separated column names, in the order of `csv_fields`.
+    const CSV_HEADER: &'static str;
+
+    /// Unquoted field values, empty for missing values.
+    fn csv_fields(&self) -> Vec<String>;
+}
+
+/// One row of machine-readable output: a single part of a single day.
+///
+/// Timings are in nanoseconds. `parse_ns` is shared by both parts of a day.
+/// Fields are `None` when the day or part failed, in which case `error`
+/// describes the failure.
+#[derive(Serialize, Debug)]
+struct PartRecord {
+    day: u32,
+    part: u8,
+    answer: Option<String>,
+    parse_ns: Option<u64>,
+    solve_ns: Option<u64>,
+    error: Option<String>,
+}
+
+impl PartRecord {
+    fn from_outcome(outcome: &Outcome) -> Vec<PartRecord> {
+        match &outcome.solution {
+            Ok(solution) => solution
+                .parts()
+                .iter()
+                .map(|(part, result)| PartRecord {
+                    day: outcome.day,
+                    part: *part,
+                    answer: result.answer.as_ref().ok().cloned(),
+                    parse_ns: Some(solution.parse_time.as_nanos() as u64),
+                    solve_ns: Some(result.time.as_nanos() as u64),
+                    error: result.answer.as_ref().err().map(|e| format!("{:#}", e)),
+                })
+                .collect(),
+            Err(e) => outcome
+                .parts
+                .iter()
+                .map(|part| PartRecord {
+                    day: outcome.day,
+                    part,
+                    answer: None,
+                    parse_ns: None,
+                    solve_ns: None,
+                    error: Some(format!("{:#}", e)),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl Record for PartRecord {
+    const CSV_HEADER: &'static str = "day,part,answer,parse_ns,solve_ns,error";
+
+    fn csv_fields(&self) -> Vec<String> {
+        vec![
+            self.day.to_string(),
+            self.part.to_string(),
+            csv_opt(&self.answer),
+            csv_opt(&self.parse_ns),
+            csv_opt(&self.solve_ns),
+            csv_opt(&self.error),
+        ]
+    }
+}
+
+/// Format an optional value as a CSV field, empty if absent.
+pub fn csv_opt<T: ToString>(value: &Option<T>) -> String {
+    value.as_ref().map(T::to_string).unwrap_or_default()
+}
+
+/// Quote a CSV field if it contains a separator, quote or newline.
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_owned()
+    }
+}
+
+/// Print warnings for any input skipped while parsing to stderr, keeping
+/// stdout machine-readable.
+fn print_warnings(outcome: &Outcome) {
+    if let Ok(solution) = &outcome.solution {
+        for warning in &solution.warnings {
+            eprintln!("warning: {}\n", warning);
+        }
+    }
+}
+
+/// Print the outcome of running a single day.
+pub fn print_day(format: Format, outcome: &Outcome) -> ProblemResult {
+    print_warnings(outcome);
+    match (format, &outcome.solution) {
+        (Format::Text, Ok(solution)) => {
+            solution.print();
+            Ok(())
+        }
+        // Text output for a day that failed to parse is just the error.
+        (Format::Text, Err(_)) => Ok(()),
+        _ => print_records(format, &PartRecord::from_outcome(outcome)),
+    }
+}
+
+/// Print the outcomes of running several days.
+pub fn print_days(format: Format, outcomes: &[Outcome]) -> ProblemResult {
+    outcomes.iter().for_each(print_warnings);
+    match format {
+        Format::Text => {
+            print!("{}", render_table(outcomes));
+            Ok(())
+        }
+        _ => {
+            let records: Vec<PartRecord> =
+                outcomes.iter().flat_map(PartRecord::from_outcome).collect();
+            print_records(format, &records)
+        }
+    }
+}
+
+/// Print records as JSON or CSV.
+pub fn print_records<R: Record>(format: Format, records: &[R]) -> ProblemResult {
+    match format {
+        Format::Json => println!("{}", serde_json::to_string_pretty(records)?),
+        Format::Csv => {
+            println!("{}", R::CSV_HEADER);
+            for record in records {
+                let fields: Vec<String> =
+                    record.csv_fields().iter().map(|f| csv_field(f)).collect();
+                println!("{}", fields.join(","));
+            }
+        }
+        Format::Text => unreachable!("text output is not record-based"),
+    }
+
+    Ok(())
+}
+
+/// Render one aligned row per day. Days that failed report their error in
+/// place of their answers, with any multi-line details (e.g. parse
+/// diagnostics) after the table.
+fn render_table(outcomes: &[Outcome]) -> String {
+    let details = std::cell::RefCell::new(Vec::new());
+    let error = |day: u32, e: &anyhow::Error| {
+        let message = format!("{:#}", e);
+        let first = message.lines().next().unwrap_or("").to_owned();
+        if message.contains('\n') {
+            details
+                .borrow_mut()
+                .push(format!("Day {}: {}", day, message));
+        }
+        format!("error: {}", first)
+    };
+    let answer = |day: u32, r: Option<&PartResult>| match r.map(|r| &r.answer) {
+        Some(Ok(a)) => a.clone(),
+        Some(Err(e)) => error(day, e),
+        None => "-".to_owned(),
+    };
+    let time = |r: Option<&PartResult>| r.map_or("-".to_owned(), |r| format_duration(r.time));
+
+    let rows: Vec<Vec<String>> = outcomes
+        .iter()
+        .map(|outcome| match &outcome.solution {
+            Ok(s) => vec![
+                outcome.day.to_string(),
+                answer(outcome.day, s.part(1)),
+                answer(outcome.day, s.part(2)),
+                format_duration(s.parse_time),
+                time(s.part(1)),
+                time(s.part(2)),
+            ],
+            Err(e) => vec![outcome.day.to_string(), error(outcome.day, e)],
+        })
+        .collect();
+
+    let mut table = render_aligned(
+        &[
+            "Day",
+            "Part 1",
+            "Part 2",
+            "Parse",
+            "Part 1 time",
+            "Part 2 time",
+        ],
+        &[
+            Align::Right,
+            Align::Left,
+            Align::Left,
+            Align::Right,
+            Align::Right,
+            Align::Right,
+        ],
+        &rows,
+    );
+
+    for detail in details.into_inner() {
+        table.push_str(&format!("\n{}\n", detail));
+    }
+    table
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum Align {
+    Left,
+    Right,
+}
+
+/// Print rows as an aligned table under a header.
+///
+/// A row with fewer cells than the header is treated as a message: its last
+/// cell spans the remaining columns and does not affect column widths.
+pub fn print_aligned(header: &[&str], align: &[Align], rows: &[Vec<String>]) {
+    print!("{}", render_aligned(header, align, rows));
+}
+
+fn render_aligned(header: &[&str], align: &[Align], rows: &[Vec<String>]) -> String {
+    let mut out = String::new();
+    let mut widths: Vec<usize> = header.iter().map(|h| h.chars().count()).collect();
+    for row in rows.iter().filter(|row| row.len() == header.len()) {
+        for (width, cell) in widths.iter_mut().zip(row.iter()) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let header: Vec<String> = header
+        .iter()
+        .zip(widths.iter())
+        .map(|(h, &w)| format!("{:<w$}", h, w = w))
+        .collect();
+    let _ = writeln!(out, "{}", header.join(" | ").trim_end());
+
+    let rule: Vec<String> = widths.iter().map(|&w| "-".repeat(w)).collect();
+    let _ = writeln!(out, "{}", rule.join("-+-"));
+
+    for row in rows {
+        let last = row.len() - 1;
+        let cells: Vec<String> = row
+            .iter()
+            .zip(widths.iter().zip(align.iter()))
+            .enumerate()
+            .map(|(i, (cell, (&w, align)))| match align {
+                _ if i == last && row.len() < widths.len() => cell.clone(),
+                Align::Left => format!("{:<w$}", cell, w = w),
+                Align::Right => format!("{:>w$}", cell, w = w),
+            })
+            .collect();
+        let _ = writeln!(out, "{}", cells.join(" | ").trim_end());
+    }
+    out
+}
+
+/// Format a duration with a unit suited to its magnitude.
+pub fn format_duration(d: Duration) -> String {
+    let nanos = d.as_nanos();
+    if nanos < 1_000 {
+        format!("{}ns", nanos)
+    } else if nanos < 1_000_000 {
+        format!("{:.2}µs", nanos as f64 / 1e3)
+    } else if nanos < 1_000_000_000 {
+        format!("{:.2}ms", nanos as f64 / 1e6)
+    } else {
+        format!("{:.2}s", d.as_secs_f64())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use anyhow::anyhow;
+
+    use super::{csv_field, render_table, Outcome, PartResult, Solution};
+    use crate::solver::Parts;
+    use crate::utils::ProblemResult;
+
+    #[test]
+    fn test_csv_field_quoting() {
+        assert_eq!(csv_field("1234"), "1234");
+        assert_eq!(csv_field("a, b"), "\"a, b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+    }
+
+    #[test]
+    fn test_render_table() {
+        let solved = |part1: ProblemResult<u64>| Solution {
+            parse_time: Duration::from_micros(5),
+            part1: Some(PartResult::new(part1, Duration::from_nanos(20))),
+            part2: Some(PartResult::new(Ok(12345), Duration::from_millis(3))),
+            details: None,
+            warnings: Vec::new(),
+        };
+        let outcomes = vec![
+            Outcome {
+                day: 1,
+                parts: Parts::Both,
+                solution: Ok(solved(Ok(7))),
+            },
+            Outcome {
+                day: 2,
+                parts: Parts::Both,
+                solution: Err(anyhow!("2 lines failed to parse\nline 3: bad")),
+            },
+            Outcome {
+                day: 3,
+                parts: Parts::Both,
+                solution: Ok(solved(Err(anyhow!("No answer")))),
+            },
+        ];
+        assert!(outcomes[1].failed() && outcomes[2].failed() && !outcomes[0].failed());
+
+        let expected = "\
+Day | Part 1           | Part 2 | Parse  | Part 1 time | Part 2 time
+----+------------------+--------+--------+-------------+------------
+  1 | 7                | 12345  | 5.00µs |        20ns |      3.00ms
+  2 | error: 2 lines failed to parse
+  3 | error: No answer | 12345  | 5.00µs |        20ns |      3.00ms
+
+Day 2: 2 lines failed to parse
+line 3: bad
+";
+        assert_eq!(render_table(&outcomes), expected);
+    }
+}
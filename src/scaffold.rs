@@ -0,0 +1,123 @@
+use std::path::Path;
+
+use anyhow::{anyhow, Context};
+use structopt::StructOpt;
+
+use crate::input::default_input_path;
+use crate::utils::ProblemResult;
+
+const TEMPLATE: &str = include_str!("templates/problem.rs.tmpl");
+
+#[derive(StructOpt, Debug)]
+pub struct NewDay {
+    /// Day to generate.
+    day: u32,
+
+    /// Title of the day's puzzle. Defaults to "Day N".
+    #[structopt(long = "title")]
+    title: Option<String>,
+
+    /// Root of the crate to add the day to.
+    #[structopt(long = "root", default_value = ".")]
+    root: String,
+}
+
+impl NewDay {
+    pub fn run(&self) -> ProblemResult {
+        let root = Path::new(&self.root);
+        let module = root.join(format!("src/problem{}.rs", self.day));
+        let main = root.join("src/main.rs");
+
+        if module.exists() {
+            return Err(anyhow!("{} already exists", module.display()));
+        }
+
+        let main_src = std::fs::read_to_string(&main)
+            .with_context(|| format!("Failed to read {}", main.display()))?;
+        let registered = register(&main_src, self.day)?;
+
+        let title = match &self.title {
+            Some(title) => title.clone(),
+            None => format!("Day {}", self.day),
+        };
+        std::fs::write(&module, render(self.day, &title))
+            .with_context(|| format!("Failed to write {}", module.display()))?;
+        std::fs::write(&main, registered)
+            .with_context(|| format!("Failed to write {}", main.display()))?;
+        println!("Created {}", module.display());
+
+        let inputs = root.join(default_input_path(self.day));
+        let inputs = inputs.parent().expect("input path has a directory");
+        std::fs::create_dir_all(inputs)
+            .with_context(|| format!("Failed to create directory {}", inputs.display()))?;
+        println!("Created {}", inputs.display());
+
+        Ok(())
+    }
+}
+
+/// Fill in the module template for a day.
+fn render(day: u32, title: &str) -> String {
+    TEMPLATE
+        .replace("{day}", &day.to_string())
+        .replace("{title}", &title.replace('"', "\\\""))
+}
+
+/// Add a day to the `days!` invocation in `main.rs`, keeping days in order.
+fn register(main_src: &str, day: u32) -> ProblemResult<String> {
+    let start = main_src
+        .find("days! {\n")
+        .ok_or_else(|| anyhow!("Couldn't find the days! registry in main.rs"))?
+        + "days! {\n".len();
+    let end = start
+        + main_src[start..]
+            .find('}')
+            .ok_or_else(|| anyhow!("Unterminated days! registry in main.rs"))?;
+
+    let entry = format!("    problem{}::P{},\n", day, day);
+    let mut offset = start;
+    for line in main_src[start..end].split_inclusive('\n') {
+        let existing: Option<u32> = line
+            .trim()
+            .strip_prefix("problem")
+            .and_then(|rest| rest.split("::").next())
+            .and_then(|n| n.parse().ok());
+        match existing {
+            Some(n) if n == day => return Err(anyhow!("Day {} is already registered", day)),
+            Some(n) if n > day => break,
+            _ => offset += line.len(),
+        }
+    }
+
+    let mut registered = main_src.to_owned();
+    registered.insert_str(offset, &entry);
+    Ok(registered)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{register, render};
+
+    const MAIN: &str = "days! {\n    problem1::P1,\n    problem3::P3,\n}\n";
+
+    #[test]
+    fn test_register() {
+        assert_eq!(
+            register(MAIN, 2).unwrap(),
+            "days! {\n    problem1::P1,\n    problem2::P2,\n    problem3::P3,\n}\n"
+        );
+        assert_eq!(
+            register(MAIN, 4).unwrap(),
+            "days! {\n    problem1::P1,\n    problem3::P3,\n    problem4::P4,\n}\n"
+        );
+        assert!(register(MAIN, 3).is_err());
+    }
+
+    #[test]
+    fn test_render() {
+        let module = render(6, "Lanternfish");
+        assert!(module.contains("pub struct P6 {"));
+        assert!(module.contains("const DAY: u32 = 6;"));
+        assert!(module.contains("const TITLE: &'static str = \"Lanternfish\";"));
+    }
+}
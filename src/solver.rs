@@ -0,0 +1,208 @@
+use std::fmt::Display;
+
+use structopt::clap::{App, ArgMatches};
+use structopt::StructOpt;
+
+use crate::bench::{measure, Phase, Stats};
+use crate::diagnostic::Leniency;
+use crate::input::{InputOpt, InputSource};
+use crate::report::{PartResult, Solution};
+use crate::utils::{timed, ProblemResult};
+
+/// A single day's puzzle.
+///
+/// Implementors are the day's command-line options, so any per-day flags
+/// (e.g. the input path) are available while parsing and solving.
+pub trait Solver {
+    /// Day of the advent calendar.
+    const DAY: u32;
+    /// Title of the day's puzzle.
+    const TITLE: &'static str;
+
+    /// Parsed puzzle input, shared by both parts.
+    type Input;
+    type Part1: Display;
+    type Part2: Display;
+
+    /// The day's input options.
+    fn input(&self) -> &InputOpt;
+    fn input_mut(&mut self) -> &mut InputOpt;
+
+    /// Parse the day's input. Input that fails to parse is either an error
+    /// or, if `leniency` allows, skipped with a warning.
+    fn parse(&self, leniency: &mut Leniency) -> ProblemResult<Self::Input>;
+    fn part1(&self, input: &Self::Input) -> ProblemResult<Self::Part1>;
+    fn part2(&self, input: &Self::Input) -> ProblemResult<Self::Part2>;
+
+    /// Extra text about the input to show after the answers, e.g. statistics
+    /// requested with a per-day flag.
+    fn details(&self, _input: &Self::Input) -> ProblemResult<Option<String>> {
+        Ok(None)
+    }
+
+    /// Where the day's input is read from.
+    fn source(&self) -> InputSource {
+        self.input().source(Self::DAY)
+    }
+}
+
+/// Object-safe view of a `Solver`, used by commands that operate on every day.
+pub trait Puzzle {
+    fn source(&self) -> InputSource;
+
+    fn solve(&self, parts: Parts) -> ProblemResult<Solution>;
+
+    /// Time parsing and each part separately over repeated runs.
+    fn bench(
+        &self,
+        parts: Parts,
+        warmup: usize,
+        iterations: usize,
+    ) -> ProblemResult<Vec<(Phase, Stats)>>;
+}
+
+impl<S: Solver> Puzzle for S {
+    fn source(&self) -> InputSource {
+        Solver::source(self)
+    }
+
+    fn solve(&self, parts: Parts) -> ProblemResult<Solution> {
+        let mut leniency = self.input().leniency();
+        let (input, parse_time) = timed(|| self.parse(&mut leniency));
+        let input = input?;
+
+        let part1 = parts.includes(1).then(|| {
+            let (answer, time) = timed(|| self.part1(&input));
+            PartResult::new(answer, time)
+        });
+        let part2 = parts.includes(2).then(|| {
+            let (answer, time) = timed(|| self.part2(&input));
+            PartResult::new(answer, time)
+        });
+
+        Ok(Solution {
+            parse_time,
+            part1,
+            part2,
+            details: self.details(&input)?,
+            warnings: leniency.into_warnings(),
+        })
+    }
+
+    fn bench(
+        &self,
+        parts: Parts,
+        warmup: usize,
+        iterations: usize,
+    ) -> ProblemResult<Vec<(Phase, Stats)>> {
+        let leniency = || self.input().leniency();
+        let parse = measure(warmup, iterations, || self.parse(&mut leniency()))?;
+        let mut results = vec![(Phase::Parse, parse)];
+
+        let input = self.parse(&mut leniency())?;
+        if parts.includes(1) {
+            let stats = measure(warmup, iterations, || self.part1(&input))?;
+            results.push((Phase::Part1, stats));
+        }
+        if parts.includes(2) {
+            let stats = measure(warmup, iterations, || self.part2(&input))?;
+            results.push((Phase::Part2, stats));
+        }
+
+        Ok(results)
+    }
+}
+
+/// Which parts of a day to run.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Parts {
+    Both,
+    Only(u8),
+}
+
+impl Parts {
+    pub fn includes(&self, part: u8) -> bool {
+        match self {
+            Parts::Both => true,
+            Parts::Only(p) => *p == part,
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = u8> + '_ {
+        (1..=2).filter(move |&part| self.includes(part))
+    }
+}
+
+impl From<Option<u8>> for Parts {
+    fn from(part: Option<u8>) -> Parts {
+        part.map_or(Parts::Both, Parts::Only)
+    }
+}
+
+/// A registered day, able to build its solver from command-line arguments.
+pub struct Day {
+    pub day: u32,
+    pub title: &'static str,
+    app: fn() -> App<'static, 'static>,
+    build: fn(&ArgMatches, Option<InputSource>) -> Box<dyn Puzzle>,
+}
+
+impl Day {
+    pub fn new<S: Solver + StructOpt + 'static>() -> Day {
+        Day {
+            day: S::DAY,
+            title: S::TITLE,
+            app: S::clap,
+            build: |matches, source| {
+                let mut solver = S::from_clap(matches);
+                if let Some(source) = source {
+                    solver.input_mut().set_source(source);
+                }
+                Box::new(solver)
+            },
+        }
+    }
+
+    /// Name of the day's subcommand, e.g. `p1`.
+    pub fn name(&self) -> String {
+        format!("p{}", self.day)
+    }
+
+    pub fn subcommand(&self) -> App<'static, 'static> {
+        (self.app)().name(self.name()).about(self.title)
+    }
+
+    pub fn build(&self, matches: &ArgMatches) -> Box<dyn Puzzle> {
+        (self.build)(matches, None)
+    }
+
+    /// Build the day's solver with all options at their defaults.
+    pub fn with_defaults(&self) -> Box<dyn Puzzle> {
+        self.with_args(&[])
+            .expect("no arguments are valid arguments")
+    }
+
+    /// Build the day's solver from command-line style arguments, e.g.
+    /// `["-i", "path/to/input.txt"]`.
+    pub fn with_args(&self, args: &[&str]) -> ProblemResult<Box<dyn Puzzle>> {
+        let matches = self.matches(args)?;
+        Ok((self.build)(&matches, None))
+    }
+
+    /// Build the day's solver from command-line style arguments, reading
+    /// input from `source` whatever they say.
+    pub fn with_source(
+        &self,
+        args: &[&str],
+        source: InputSource,
+    ) -> ProblemResult<Box<dyn Puzzle>> {
+        let matches = self.matches(args)?;
+        Ok((self.build)(&matches, Some(source)))
+    }
+
+    fn matches(&self, args: &[&str]) -> ProblemResult<ArgMatches<'static>> {
+        let name = self.name();
+        let argv = std::iter::once(name.as_str()).chain(args.iter().cloned());
+        Ok(self.subcommand().get_matches_from_safe(argv)?)
+    }
+}
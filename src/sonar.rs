@@ -0,0 +1,266 @@
+use std::io::{BufRead, BufReader};
+use std::thread::sleep;
+use std::time::Duration;
+
+use anyhow::anyhow;
+use serde::Serialize;
+use structopt::StructOpt;
+
+use crate::diagnostic::{message_chain, Diagnostic, Diagnostics, Leniency, Spanned};
+use crate::input::{normalize_line, InputError, InputOpt, InputSource};
+use crate::problem1::{Analysis, RunningCount};
+use crate::report::{self, Format, Record};
+use crate::solver::Parts;
+use crate::utils::mapped::FromBytes;
+use crate::utils::ProblemResult;
+
+/// Day 1's analysis over a live feed of depths, read one line at a time so
+/// that memory use doesn't grow with the input.
+#[derive(StructOpt, Debug)]
+pub struct Sonar {
+    #[structopt(flatten)]
+    input: InputOpt,
+
+    #[structopt(flatten)]
+    analysis: Analysis,
+
+    /// Keep waiting for more depths at the end of an input file, as it grows.
+    #[structopt(short = "f", long = "follow")]
+    follow: bool,
+
+    /// How often to check a followed file for more depths, in milliseconds.
+    #[structopt(long = "poll-ms", default_value = "250")]
+    poll_ms: u64,
+
+    /// Print the running counts after every N depths, as well as at the end
+    /// of the input. 1 prints them as each depth arrives.
+    #[structopt(long = "every", default_value = "0")]
+    every: usize,
+}
+
+/// The counts so far, for each selected part.
+#[derive(Debug)]
+pub struct Progress {
+    pub depths: usize,
+    pub counts: Vec<(u8, usize)>,
+}
+
+impl Progress {
+    fn print(&self) {
+        let counts: Vec<String> = self
+            .counts
+            .iter()
+            .map(|(part, count)| format!("part {} {}", part, count))
+            .collect();
+        println!("{} depths: {}", self.depths, counts.join(", "));
+    }
+
+    fn records(&self) -> impl Iterator<Item = ProgressRecord> + '_ {
+        self.counts
+            .iter()
+            .map(move |&(part, count)| ProgressRecord {
+                depths: self.depths,
+                part,
+                count,
+            })
+    }
+}
+
+/// One row of machine-readable output: a part's count after some number of
+/// depths.
+#[derive(Serialize, Debug, PartialEq)]
+struct ProgressRecord {
+    depths: usize,
+    part: u8,
+    count: usize,
+}
+
+impl Record for ProgressRecord {
+    const CSV_HEADER: &'static str = "depths,part,count";
+
+    fn csv_fields(&self) -> Vec<String> {
+        vec![
+            self.depths.to_string(),
+            self.part.to_string(),
+            self.count.to_string(),
+        ]
+    }
+}
+
+impl Sonar {
+    pub fn run(&self, parts: Parts, format: Format) -> ProblemResult {
+        let source = self.input.source(1);
+        // Only a file can grow; stdin already waits for more input.
+        let follow = self.follow && matches!(source, InputSource::Path(_));
+        let mut leniency = self.input.leniency();
+
+        if format == Format::Text {
+            let reader = BufReader::new(source.open()?);
+            let progress =
+                self.feed(reader, &source, follow, parts, &mut leniency, |p| p.print())?;
+            progress.print();
+            return Ok(());
+        }
+
+        // Records are printed as one document once the feed ends, which a
+        // followed file never does.
+        if follow {
+            return Err(anyhow!("--follow only supports --format text"));
+        }
+        let reader = BufReader::new(source.open()?);
+        let mut records = Vec::new();
+        let progress = self.feed(reader, &source, follow, parts, &mut leniency, |p| {
+            records.extend(p.records())
+        })?;
+        // The last emitted counts may already be the final ones.
+        if records.last().map(|r| r.depths) != Some(progress.depths) {
+            records.extend(progress.records());
+        }
+        report::print_records(format, &records)
+    }
+
+    /// Count changes in the depths read from `reader`, calling `emit` every
+    /// `--every` depths. Blank lines are ignored. Bad lines are skipped with a
+    /// warning if lenient, and end the feed otherwise.
+    fn feed(
+        &self,
+        mut reader: impl BufRead,
+        source: &InputSource,
+        follow: bool,
+        parts: Parts,
+        leniency: &mut Leniency,
+        mut emit: impl FnMut(&Progress),
+    ) -> ProblemResult<Progress> {
+        let mut counters: Vec<(u8, RunningCount)> = Vec::new();
+        if parts.includes(1) {
+            counters.push((1, RunningCount::new(&self.analysis, 1)));
+        }
+        if parts.includes(2) {
+            let window = self.analysis.window()?;
+            counters.push((2, RunningCount::new(&self.analysis, window)));
+        }
+        let progress = |depths: usize, counters: &[(u8, RunningCount)]| Progress {
+            depths,
+            counts: counters
+                .iter()
+                .map(|(part, c)| (*part, c.count()))
+                .collect(),
+        };
+
+        let mut line = String::new();
+        let mut lineno = 0;
+        let mut depths = 0;
+        loop {
+            reader.read_line(&mut line).map_err(|e| InputError::Read {
+                name: source.to_string(),
+                source: e,
+            })?;
+            // A followed file may end part way through a line that's still
+            // being written, so wait for the rest of it.
+            if !line.ends_with('\n') {
+                if follow {
+                    sleep(Duration::from_millis(self.poll_ms));
+                    continue;
+                }
+                if line.is_empty() {
+                    break;
+                }
+            }
+            lineno += 1;
+
+            match parse_depth(&line, lineno, source, leniency) {
+                Ok(Some(depth)) => {
+                    counters.iter_mut().for_each(|(_, c)| c.push(depth));
+                    depths += 1;
+                    if self.every > 0 && depths % self.every == 0 {
+                        emit(&progress(depths, &counters));
+                    }
+                }
+                Ok(None) => {}
+                Err(diagnostic) if leniency.is_lenient() => eprintln!("warning: {}\n", diagnostic),
+                Err(diagnostic) => return Err(Diagnostics(vec![diagnostic]).into()),
+            }
+            line.clear();
+        }
+
+        Ok(progress(depths, &counters))
+    }
+}
+
+/// Parse a line of a feed as a depth, or `None` if it's blank.
+fn parse_depth(
+    line: &str,
+    lineno: usize,
+    source: &InputSource,
+    leniency: &Leniency,
+) -> Result<Option<u32>, Diagnostic> {
+    let (normalized, issue) = normalize_line(line.strip_suffix('\n').unwrap_or(line));
+    if let (true, Some(issue)) = (leniency.is_strict(), issue) {
+        return Err(issue.diagnostic(source, lineno, line.trim_end_matches('\n')));
+    }
+    if normalized.is_empty() {
+        return Ok(None);
+    }
+
+    if let Some(depth) = u32::from_bytes(normalized.as_bytes()) {
+        return Ok(Some(depth));
+    }
+    normalized.parse().map(Some).map_err(|e| Diagnostic {
+        name: source.to_string(),
+        lineno,
+        line: normalized.to_owned(),
+        span: Spanned::span(&e),
+        message: message_chain(&e),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ProgressRecord, Sonar};
+    use crate::diagnostic::Leniency;
+    use crate::input::InputSource;
+    use crate::solver::Parts;
+    use structopt::StructOpt;
+
+    #[test]
+    fn test_feed() {
+        let input = &b"199\n200\n208\n210\n\n200\n207\n240\n269\n260\n263"[..];
+        let source = InputSource::Text(String::new());
+        let sonar = Sonar::from_iter(&["sonar", "--every", "4"]);
+
+        let mut emitted = Vec::new();
+        let progress = sonar
+            .feed(
+                input,
+                &source,
+                false,
+                Parts::Both,
+                &mut Leniency::default(),
+                |p| emitted.push((p.depths, p.counts.clone())),
+            )
+            .unwrap();
+        assert_eq!(progress.depths, 10);
+        assert_eq!(progress.counts, vec![(1, 7), (2, 5)]);
+        assert_eq!(
+            emitted,
+            vec![(4, vec![(1, 3), (2, 1)]), (8, vec![(1, 6), (2, 3)])]
+        );
+        let records: Vec<ProgressRecord> = progress.records().collect();
+        assert_eq!(
+            records[1],
+            ProgressRecord {
+                depths: 10,
+                part: 2,
+                count: 5
+            }
+        );
+
+        let input = &b"199\nx\n200\n"[..];
+        let feed = |leniency: &mut Leniency| {
+            sonar.feed(input, &source, false, Parts::Only(1), leniency, |_| {})
+        };
+        assert!(feed(&mut Leniency::default()).is_err());
+        let progress = feed(&mut Leniency::new(true)).unwrap();
+        assert_eq!(progress.counts, vec![(1, 1)]);
+    }
+}
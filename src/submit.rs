@@ -0,0 +1,382 @@
+use std::fs::OpenOptions;
+use std::io::{BufRead, BufReader, Write};
+
+use anyhow::{anyhow, Context};
+use serde::{Deserialize, Serialize};
+use structopt::StructOpt;
+
+use crate::client::ClientOpt;
+use crate::history::now;
+use crate::solver::{Day, Parts};
+use crate::utils::ProblemResult;
+
+#[derive(StructOpt, Debug)]
+pub struct Submit {
+    /// Day whose answer to submit. The part is given by the global `--part`.
+    #[structopt(long = "day")]
+    day: u32,
+
+    /// Log of past submissions, used to avoid resubmitting known-wrong
+    /// answers or submitting while rate-limited.
+    #[structopt(long = "log", default_value = "submissions.jsonl")]
+    log: String,
+
+    #[structopt(flatten)]
+    client: ClientOpt,
+}
+
+/// The server's judgement of a submitted answer.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Verdict {
+    Correct,
+    TooHigh,
+    TooLow,
+    Wrong,
+    /// Submitted too soon after a previous answer; nothing was judged.
+    RateLimited,
+    /// The part was already solved, so nothing was judged.
+    AlreadySolved,
+    Unrecognized,
+}
+
+impl Verdict {
+    fn is_wrong(&self) -> bool {
+        matches!(self, Verdict::TooHigh | Verdict::TooLow | Verdict::Wrong)
+    }
+}
+
+/// A verdict along with how long the server asked us to wait before
+/// submitting again, if it said.
+#[derive(Debug, PartialEq)]
+pub struct Response {
+    pub verdict: Verdict,
+    pub wait_secs: Option<u64>,
+}
+
+/// One line of the submission log.
+#[derive(Serialize, Deserialize, Debug)]
+struct Submission {
+    timestamp: u64,
+    year: u32,
+    day: u32,
+    part: u8,
+    answer: String,
+    verdict: Verdict,
+    wait_secs: Option<u64>,
+}
+
+impl Submit {
+    pub fn run(&self, days: &[Day], parts: Parts) -> ProblemResult {
+        let part = match parts {
+            Parts::Only(part) => part,
+            Parts::Both => return Err(anyhow!("Choose a part to submit with --part")),
+        };
+        let day = days
+            .iter()
+            .find(|d| d.day == self.day)
+            .ok_or_else(|| anyhow!("Day {} is not registered", self.day))?;
+
+        let solution = day.with_defaults().solve(parts)?;
+        let answer = match &solution.part(part).expect("selected part was run").answer {
+            Ok(answer) => answer.clone(),
+            Err(e) => return Err(anyhow!("Part {} failed: {:#}", part, e)),
+        };
+
+        let client = self.client.client()?;
+        let log = load_log(&self.log)?;
+        let past: Vec<&Submission> = log
+            .iter()
+            .filter(|s| s.year == client.year() && s.day == self.day && s.part == part)
+            .collect();
+
+        if let Some(correct) = past.iter().find(|s| s.verdict == Verdict::Correct) {
+            if correct.answer == answer {
+                println!(
+                    "Day {} part {}: {} is already known to be correct",
+                    self.day, part, answer
+                );
+                return Ok(());
+            }
+            return Err(anyhow!(
+                "Day {} part {} was already solved with {}, but the answer is now {}",
+                self.day,
+                part,
+                correct.answer,
+                answer
+            ));
+        }
+        check_known_wrong(&past, &answer)?;
+        check_rate_limit(&log)?;
+
+        let reply = client.submit(self.day, part, &answer)?;
+        let response = parse_response(&reply);
+        append_log(
+            &self.log,
+            &Submission {
+                timestamp: now(),
+                year: client.year(),
+                day: self.day,
+                part,
+                answer: answer.clone(),
+                verdict: response.verdict,
+                wait_secs: response.wait_secs,
+            },
+        )?;
+
+        let wait = response
+            .wait_secs
+            .map(|s| format!(" (wait {}s before submitting again)", s))
+            .unwrap_or_default();
+        let message = match response.verdict {
+            Verdict::Correct => {
+                println!("Day {} part {}: {} is correct!", self.day, part, answer);
+                return Ok(());
+            }
+            Verdict::TooHigh => "is too high",
+            Verdict::TooLow => "is too low",
+            Verdict::Wrong => "is wrong",
+            Verdict::RateLimited => "was not judged: submitted too recently",
+            Verdict::AlreadySolved => "was not judged: part already solved",
+            Verdict::Unrecognized => "got an unrecognized response",
+        };
+
+        Err(anyhow!(
+            "Day {} part {}: {} {}{}",
+            self.day,
+            part,
+            answer,
+            message,
+            wait
+        ))
+    }
+}
+
+/// Refuse answers already judged wrong, or outside bounds implied by earlier
+/// too-high or too-low verdicts.
+fn check_known_wrong(past: &[&Submission], answer: &str) -> ProblemResult {
+    if past
+        .iter()
+        .any(|s| s.answer == answer && s.verdict.is_wrong())
+    {
+        return Err(anyhow!("{} was already submitted and is wrong", answer));
+    }
+
+    if let Ok(value) = answer.parse::<i128>() {
+        for s in past {
+            let previous = match s.answer.parse::<i128>() {
+                Ok(v) => v,
+                Err(_) => continue,
+            };
+            match s.verdict {
+                Verdict::TooHigh if value >= previous => {
+                    return Err(anyhow!("{} is too high: {} already was", answer, previous))
+                }
+                Verdict::TooLow if value <= previous => {
+                    return Err(anyhow!("{} is too low: {} already was", answer, previous))
+                }
+                _ => {}
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Refuse to submit while a wait requested by the server is still running.
+/// The server rate-limits across all days, so every submission counts.
+fn check_rate_limit(log: &[Submission]) -> ProblemResult {
+    let now = now();
+    let wait_until = log
+        .iter()
+        .filter_map(|s| s.wait_secs.map(|w| s.timestamp + w))
+        .max();
+
+    match wait_until {
+        Some(until) if until > now => Err(anyhow!(
+            "Rate limited: wait {}s before submitting again",
+            until - now
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Work out the verdict from the text of the server's reply.
+pub fn parse_response(body: &str) -> Response {
+    // Only the <article> holds the message; the rest is page chrome.
+    let text = match (body.find("<article>"), body.find("</article>")) {
+        (Some(start), Some(end)) if start < end => &body[start..end],
+        _ => body,
+    };
+
+    let verdict = if text.contains("That's the right answer") {
+        Verdict::Correct
+    } else if text.contains("You gave an answer too recently") {
+        Verdict::RateLimited
+    } else if text.contains("not the right answer") {
+        if text.contains("too high") {
+            Verdict::TooHigh
+        } else if text.contains("too low") {
+            Verdict::TooLow
+        } else {
+            Verdict::Wrong
+        }
+    } else if text.contains("Did you already complete it") {
+        Verdict::AlreadySolved
+    } else {
+        Verdict::Unrecognized
+    };
+
+    Response {
+        verdict,
+        wait_secs: parse_wait(text),
+    }
+}
+
+/// Find a wait in either of the server's phrasings: "you have 1m 30s left to
+/// wait" or "please wait one minute before trying again".
+fn parse_wait(text: &str) -> Option<u64> {
+    if let Some(end) = text.find(" left to wait") {
+        let words: Vec<&str> = text[..end]
+            .rsplit("have ")
+            .next()?
+            .split_whitespace()
+            .collect();
+        let mut secs = 0;
+        for word in words {
+            let (num, unit) = word.split_at(word.find(|c: char| !c.is_ascii_digit())?);
+            let num: u64 = num.parse().ok()?;
+            secs += match unit {
+                "h" => num * 3600,
+                "m" => num * 60,
+                "s" => num,
+                _ => return None,
+            };
+        }
+        return Some(secs);
+    }
+
+    let lower = text.to_lowercase();
+    let rest = &lower[lower.find("please wait ")? + "please wait ".len()..];
+    let mut words = rest.split_whitespace();
+    let count = match words.next()? {
+        "one" => 1,
+        n => n.parse().ok()?,
+    };
+    match words.next()? {
+        "minute" | "minutes" => Some(count * 60),
+        "second" | "seconds" => Some(count),
+        _ => None,
+    }
+}
+
+fn load_log(path: &str) -> ProblemResult<Vec<Submission>> {
+    let file = match std::fs::File::open(path) {
+        Ok(f) => f,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path)),
+    };
+
+    let mut log = Vec::new();
+    for (lineno, line) in BufReader::new(file).lines().enumerate() {
+        let line = line.with_context(|| format!("Failed to read {}", path))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let entry = serde_json::from_str(&line)
+            .with_context(|| format!("Failed to parse {} at line {}", path, lineno + 1))?;
+        log.push(entry);
+    }
+    Ok(log)
+}
+
+fn append_log(path: &str, submission: &Submission) -> ProblemResult {
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("Failed to open {}", path))?;
+    writeln!(file, "{}", serde_json::to_string(submission)?)
+        .with_context(|| format!("Failed to write {}", path))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check_known_wrong, parse_response, Response, Submission, Verdict};
+
+    fn article(text: &str) -> String {
+        format!(
+            "<html><main><article><p>{}</p></article></main></html>",
+            text
+        )
+    }
+
+    #[test]
+    fn test_parse_response() {
+        let cases = [
+            (
+                "That's the right answer! You are one gold star closer.",
+                Verdict::Correct,
+                None,
+            ),
+            (
+                "That's not the right answer; your answer is too high. Please wait one minute before trying again.",
+                Verdict::TooHigh,
+                Some(60),
+            ),
+            (
+                "That's not the right answer; your answer is too low. please wait 5 minutes before trying again.",
+                Verdict::TooLow,
+                Some(300),
+            ),
+            (
+                "You gave an answer too recently; you have to wait after submitting an answer before trying again.  You have 4m 49s left to wait.",
+                Verdict::RateLimited,
+                Some(289),
+            ),
+            (
+                "You don't seem to be solving the right level.  Did you already complete it?",
+                Verdict::AlreadySolved,
+                None,
+            ),
+        ];
+
+        for (text, verdict, wait_secs) in cases.iter() {
+            assert_eq!(
+                parse_response(&article(text)),
+                Response {
+                    verdict: *verdict,
+                    wait_secs: *wait_secs
+                },
+                "{}",
+                text
+            );
+        }
+    }
+
+    #[test]
+    fn test_known_wrong() {
+        let submission = |answer: &str, verdict| Submission {
+            timestamp: 0,
+            year: 2021,
+            day: 1,
+            part: 1,
+            answer: answer.to_owned(),
+            verdict,
+            wait_secs: None,
+        };
+        let log = [
+            submission("100", Verdict::TooHigh),
+            submission("10", Verdict::TooLow),
+            submission("50", Verdict::Wrong),
+        ];
+        let past: Vec<&Submission> = log.iter().collect();
+
+        assert!(check_known_wrong(&past, "100").is_err());
+        assert!(check_known_wrong(&past, "150").is_err());
+        assert!(check_known_wrong(&past, "5").is_err());
+        assert!(check_known_wrong(&past, "50").is_err());
+        assert!(check_known_wrong(&past, "51").is_ok());
+    }
+}
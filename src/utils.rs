@@ -1,65 +1,518 @@
-use std::fmt::Debug;
-use std::fs::File;
+use std::fmt::{Debug, Display};
 
-use std::io::{BufRead, BufReader, Error as IOError, Read};
+use std::collections::VecDeque;
+use std::io::{BufRead, BufReader, Error as IOError, ErrorKind, Read, Split};
+use std::marker::PhantomData;
 use std::str::FromStr;
+use std::time::{Duration, Instant};
 
 use thiserror::Error;
 
+use crate::diagnostic::{message_chain, Diagnostic, Diagnostics, Leniency, Spanned};
+use crate::input::{normalize_line, InputError, InputSource, Issue, IssueKind};
+use mapped::{parse_mapped_ints, parse_mapped_lines, FromBytes, MappedInput};
+
+pub mod combinator;
+pub mod grid;
+pub mod mapped;
+pub mod pattern;
+
 pub type ProblemResult<T = ()> = anyhow::Result<T>;
 
 #[derive(Error, Debug)]
 pub enum ParseLinesError<T>
 where
     T: FromStr,
-    <T as FromStr>::Err: std::error::Error + Debug + 'static,
+    <T as FromStr>::Err: std::error::Error + Spanned + Debug + 'static,
 {
-    #[error("Failed to open file: {path}")]
-    FileOpen { path: String, source: IOError },
+    #[error(transparent)]
+    Input(#[from] InputError),
 
-    #[error("Failed reading line {lineno}")]
-    LineRead { source: IOError, lineno: usize },
+    #[error("Failed reading line {lineno} of {name}")]
+    LineRead {
+        source: IOError,
+        name: String,
+        lineno: usize,
+    },
 
-    #[error("Failed parsing line {lineno}: {line:?}")]
+    #[error("{diagnostic}")]
     Parse {
-        lineno: usize,
-        line: String,
-        source: <T as FromStr>::Err,
+        diagnostic: Diagnostic,
+        error: <T as FromStr>::Err,
     },
+
+    #[error("{0}")]
+    Format(Diagnostic),
+
+    #[error(transparent)]
+    Invalid(#[from] Diagnostics),
 }
 
-/// Read lines from a file and parse into a sequence of values.
-pub fn parse_lines_from_path<T>(path: &str) -> Result<Vec<T>, ParseLinesError<T>>
+/// Read lines from an input source and parse into a sequence of values.
+///
+/// Files are memory-mapped and parsed in place. Every line is parsed, so that
+/// all bad lines can be reported together; with a lenient `leniency` they're
+/// skipped instead.
+pub fn parse_lines_from_source<T>(
+    source: &InputSource,
+    leniency: &mut Leniency,
+) -> Result<Vec<T>, ParseLinesError<T>>
 where
     T: FromStr,
-    <T as FromStr>::Err: std::error::Error + Send + Sync + 'static,
+    <T as FromStr>::Err: std::error::Error + Spanned + Send + Sync + 'static,
+{
+    let input = MappedInput::open(source)?;
+    parse_mapped_lines(&input, &source.to_string(), leniency)
+}
+
+/// Like `parse_lines_from_source`, with a fast path for lines of integers.
+pub fn parse_ints_from_source<T>(
+    source: &InputSource,
+    leniency: &mut Leniency,
+) -> Result<Vec<T>, ParseLinesError<T>>
+where
+    T: FromBytes,
+    <T as FromStr>::Err: std::error::Error + Spanned + Send + Sync + 'static,
 {
-    parse_lines(
-        File::open(path).map_err(|source| ParseLinesError::FileOpen {
-            path: path.to_owned(),
-            source,
-        })?,
-    )
+    let input = MappedInput::open(source)?;
+    parse_mapped_ints(&input, &source.to_string(), leniency)
+}
+
+/// Read a whole input for parsers that don't work line by line, normalized
+/// as by `iter_lines`.
+pub fn read_text(source: &InputSource, leniency: &Leniency) -> ProblemResult<String> {
+    let raw = source.read_to_string()?;
+    let mut text = String::with_capacity(raw.len());
+    let mut issues = Vec::new();
+
+    let lines: Vec<&str> = raw.strip_suffix('\n').unwrap_or(&raw).split('\n').collect();
+    for (index, line) in lines.iter().enumerate() {
+        let (normalized, issue) = normalize_line(line);
+        if let Some(issue) = issue {
+            issues.push(issue.diagnostic(source, index + 1, line));
+        }
+        text.push_str(normalized);
+        text.push('\n');
+    }
+
+    let trailing = lines
+        .iter()
+        .rev()
+        .take_while(|l| l.trim().is_empty())
+        .count();
+    if trailing > 0 && trailing < lines.len() {
+        let issue = Issue {
+            kind: IssueKind::TrailingBlankLines,
+            span: 0..0,
+        };
+        let lineno = lines.len() - trailing + 1;
+        issues.push(issue.diagnostic(source, lineno, ""));
+        text.truncate(text.trim_end_matches('\n').len() + 1);
+    }
+
+    if leniency.is_strict() && !issues.is_empty() {
+        return Err(Diagnostics(issues).into());
+    }
+    Ok(text)
 }
 
 /// Split a readable sequence into lines and parse into a sequence of values.
+#[cfg(test)]
 pub fn parse_lines<T>(readable: impl Read) -> Result<Vec<T>, ParseLinesError<T>>
 where
     T: FromStr,
-    <T as FromStr>::Err: std::error::Error + Send + Sync + 'static,
+    <T as FromStr>::Err: std::error::Error + Spanned + Send + Sync + 'static,
+{
+    iter_lines(readable).collect()
+}
+
+/// Lazily split a readable sequence into lines, parsing each as it's reached.
+///
+/// Lines are normalized first: trailing whitespace, including the `\r` of a
+/// `\r\n` line ending, is stripped, and blank lines at the end of the input
+/// are dropped. In strict mode these are reported as errors instead.
+pub fn iter_lines<T, R>(readable: R) -> ParseLines<T, R>
+where
+    T: FromStr,
+    <T as FromStr>::Err: std::error::Error + Spanned + Send + Sync + 'static,
+    R: Read,
+{
+    ParseLines {
+        lines: BufReader::new(readable).split(b'\n').enumerate(),
+        name: "<input>".to_owned(),
+        strict: false,
+        blank: Vec::new(),
+        ready: VecDeque::new(),
+        _marker: PhantomData,
+    }
+}
+
+/// Iterator returned by `iter_lines`.
+pub struct ParseLines<T, R> {
+    lines: std::iter::Enumerate<Split<BufReader<R>>>,
+    name: String,
+    strict: bool,
+    /// Line numbers of blank lines not yet known to be followed by more input.
+    blank: Vec<usize>,
+    /// Lines ready to be parsed.
+    ready: VecDeque<(usize, String)>,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T, R> ParseLines<T, R> {
+    /// Name the input in errors, e.g. with its path.
+    pub fn named(mut self, name: String) -> Self {
+        self.name = name;
+        self
+    }
+
+    /// Report formatting problems as errors rather than fixing them.
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+}
+
+impl<T, R> Iterator for ParseLines<T, R>
+where
+    T: FromStr,
+    <T as FromStr>::Err: std::error::Error + Spanned + Send + Sync + 'static,
+    R: Read,
+{
+    type Item = Result<T, ParseLinesError<T>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.ready.is_empty() {
+            let (index, maybe_line) = match self.lines.next() {
+                Some(next) => next,
+                None if self.strict && !self.blank.is_empty() => {
+                    let issue = Issue {
+                        kind: IssueKind::TrailingBlankLines,
+                        span: 0..0,
+                    };
+                    let diagnostic = issue.diagnostic(&self.name, self.blank[0], "");
+                    self.blank.clear();
+                    return Some(Err(ParseLinesError::Format(diagnostic)));
+                }
+                None => return None,
+            };
+            let lineno = index + 1;
+            let line = match maybe_line.and_then(|bytes| {
+                String::from_utf8(bytes).map_err(|e| IOError::new(ErrorKind::InvalidData, e))
+            }) {
+                Ok(line) => line,
+                Err(source) => {
+                    return Some(Err(ParseLinesError::LineRead {
+                        source,
+                        name: self.name.clone(),
+                        lineno,
+                    }))
+                }
+            };
+
+            let (normalized, issue) = normalize_line(&line);
+            if let (true, Some(issue)) = (self.strict, issue) {
+                let diagnostic = issue.diagnostic(&self.name, lineno, &line);
+                return Some(Err(ParseLinesError::Format(diagnostic)));
+            }
+
+            // Blank lines are only parsed if more input follows them.
+            if normalized.is_empty() {
+                self.blank.push(lineno);
+            } else {
+                let blank = self.blank.drain(..).map(|n| (n, String::new()));
+                self.ready.extend(blank);
+                self.ready.push_back((lineno, normalized.to_owned()));
+            }
+        }
+
+        let (lineno, line) = self.ready.pop_front()?;
+        Some(T::from_str(&line).map_err(|error| ParseLinesError::Parse {
+            diagnostic: Diagnostic {
+                name: self.name.clone(),
+                lineno,
+                span: error.span(),
+                message: message_chain(&error),
+                line,
+            },
+            error,
+        }))
+    }
+}
+
+/// Run `f` over the successful values of a fallible iterator, stopping at and
+/// returning the first error.
+pub fn process_results<T, E, R>(
+    iter: impl Iterator<Item = Result<T, E>>,
+    f: impl FnOnce(&mut dyn Iterator<Item = T>) -> R,
+) -> Result<R, E> {
+    let mut error = None;
+    let result = f(&mut iter.map_while(|r| r.map_err(|e| error = Some(e)).ok()));
+    match error {
+        Some(e) => Err(e),
+        None => Ok(result),
+    }
+}
+
+/// A day's parsed lines, either loaded up front or re-read lazily from the
+/// source each time they're used so that large inputs take constant memory.
+///
+/// Streamed lines are parsed as they're used, so only the first bad line is
+/// reported.
+#[derive(Debug)]
+pub enum LineValues<T> {
+    /// The values, and the line numbers of any lines skipped as bad, in order.
+    Loaded {
+        values: Vec<T>,
+        skipped: Vec<usize>,
+    },
+    Streamed {
+        source: InputSource,
+        strict: bool,
+    },
+}
+
+impl<T> LineValues<T>
+where
+    T: FromStr + Clone + Debug + 'static,
+    <T as FromStr>::Err: std::error::Error + Spanned + Send + Sync + 'static,
+{
+    pub fn from_source(
+        source: InputSource,
+        stream: bool,
+        leniency: &mut Leniency,
+    ) -> ProblemResult<LineValues<T>> {
+        Self::load(source, stream, leniency, parse_lines_from_source)
+    }
+
+    /// Load or stream values, loading them with `parse` unless streaming.
+    fn load(
+        source: InputSource,
+        stream: bool,
+        leniency: &mut Leniency,
+        parse: impl FnOnce(&InputSource, &mut Leniency) -> Result<Vec<T>, ParseLinesError<T>>,
+    ) -> ProblemResult<LineValues<T>> {
+        match source {
+            // Each use re-reads the input, which stdin can't do.
+            InputSource::Stdin if stream => Err(anyhow::anyhow!("Can't stream input from stdin")),
+            _ if stream && leniency.is_lenient() => {
+                Err(anyhow::anyhow!("Can't skip bad lines of streamed input"))
+            }
+            source if stream => Ok(LineValues::Streamed {
+                source,
+                strict: leniency.is_strict(),
+            }),
+            source => {
+                // Each warning is for a line that was skipped.
+                let before = leniency.warnings().len();
+                let values = parse(&source, leniency)?;
+                let skipped = leniency.warnings()[before..]
+                    .iter()
+                    .map(|w| w.lineno)
+                    .collect();
+                Ok(LineValues::Loaded { values, skipped })
+            }
+        }
+    }
+
+    /// Run `f` over the values, failing if any line can't be read or parsed.
+    pub fn process<R>(&self, f: impl FnOnce(&mut dyn Iterator<Item = T>) -> R) -> ProblemResult<R> {
+        match self {
+            LineValues::Loaded { values, .. } => Ok(f(&mut values.iter().cloned())),
+            LineValues::Streamed { source, strict } => {
+                let values = iter_lines(source.open()?)
+                    .named(source.to_string())
+                    .strict(*strict);
+                Ok(process_results(values, f)?)
+            }
+        }
+    }
+
+    /// Like `process`, pairing each value with its 1-based line number.
+    pub fn process_numbered<R>(
+        &self,
+        f: impl FnOnce(&mut dyn Iterator<Item = (usize, T)>) -> R,
+    ) -> ProblemResult<R> {
+        match self {
+            LineValues::Loaded { values, skipped } => {
+                let mut skipped = skipped.iter().peekable();
+                let mut lineno = 0;
+                Ok(f(&mut values.iter().cloned().map(|value| {
+                    lineno += 1;
+                    while skipped.next_if_eq(&&lineno).is_some() {
+                        lineno += 1;
+                    }
+                    (lineno, value)
+                })))
+            }
+            // Streamed input can't skip lines, so every line is a value.
+            LineValues::Streamed { .. } => {
+                self.process(|values| f(&mut values.enumerate().map(|(i, value)| (i + 1, value))))
+            }
+        }
+    }
+}
+
+impl<T> LineValues<T>
+where
+    T: FromBytes + Clone + Debug + 'static,
+    <T as FromStr>::Err: std::error::Error + Spanned + Send + Sync + 'static,
+{
+    /// Like `from_source`, loading integers with a fast path.
+    pub fn ints_from_source(
+        source: InputSource,
+        stream: bool,
+        leniency: &mut Leniency,
+    ) -> ProblemResult<LineValues<T>> {
+        Self::load(source, stream, leniency, parse_ints_from_source)
+    }
+}
+
+/// A run of consecutive non-blank lines.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Block<'a> {
+    /// 1-based line number of the block's first line.
+    pub lineno: usize,
+    pub lines: Vec<&'a str>,
+}
+
+impl Block<'_> {
+    /// Parse the block's lines, joined with `\n`.
+    pub fn parse<T>(&self, name: &dyn Display) -> Result<T, Diagnostic>
+    where
+        T: FromStr,
+        <T as FromStr>::Err: std::error::Error + Spanned,
+    {
+        T::from_str(&self.lines.join("\n")).map_err(|e| Diagnostic {
+            name: name.to_string(),
+            lineno: self.lineno + e.line(),
+            line: self.lines.get(e.line()).unwrap_or(&"").to_string(),
+            span: e.span(),
+            message: message_chain(&e),
+        })
+    }
+}
+
+/// Split text into blocks separated by one or more blank lines. Lines may end
+/// in `\n` or `\r\n`, and lines of only whitespace count as blank.
+pub fn split_blocks(text: &str) -> Vec<Block<'_>> {
+    let mut blocks = Vec::new();
+    let mut current: Option<Block> = None;
+
+    for (index, line) in text.lines().enumerate() {
+        if line.trim().is_empty() {
+            blocks.extend(current.take());
+        } else {
+            current
+                .get_or_insert_with(|| Block {
+                    lineno: index + 1,
+                    lines: Vec::new(),
+                })
+                .lines
+                .push(line);
+        }
+    }
+    blocks.extend(current);
+
+    blocks
+}
+
+/// A header block followed by any number of blocks of the same type, e.g.
+/// bingo draws followed by boards.
+#[derive(Debug)]
+pub struct Blocks<H, T> {
+    pub header: H,
+    /// Each block along with the line it starts on.
+    pub blocks: Vec<(usize, T)>,
+}
+
+/// Split text into blocks and parse them, reporting every block that fails
+/// to parse, or skipping them if `leniency` allows. The header can't be
+/// skipped.
+pub fn parse_blocks<H, T>(
+    text: &str,
+    name: impl Display,
+    leniency: &mut Leniency,
+) -> Result<Blocks<H, T>, Diagnostics>
+where
+    H: FromStr,
+    <H as FromStr>::Err: std::error::Error + Spanned,
+    T: FromStr,
+    <T as FromStr>::Err: std::error::Error + Spanned,
 {
-    let lines = BufReader::new(readable).lines();
-    let mut vals: Vec<T> = Vec::new();
+    let blocks = split_blocks(text);
+    let (header, rest) = match blocks.split_first() {
+        Some(split) => split,
+        None => {
+            return Err(Diagnostics(vec![Diagnostic {
+                name: name.to_string(),
+                lineno: 1,
+                line: String::new(),
+                span: None,
+                message: "Empty input".to_owned(),
+            }]))
+        }
+    };
+    let header = header.parse(&name).map_err(|e| Diagnostics(vec![e]))?;
 
-    for (lineno, maybe_line) in lines.enumerate() {
-        let line = maybe_line.map_err(|e| ParseLinesError::LineRead { source: e, lineno })?;
-        let val = T::from_str(&line).map_err(|e| ParseLinesError::Parse {
-            source: e,
-            lineno,
-            line,
-        })?;
-        vals.push(val);
+    let mut parsed = Vec::new();
+    let mut errors = Vec::new();
+    for block in rest {
+        match block.parse(&name) {
+            Ok(value) => parsed.push((block.lineno, value)),
+            Err(e) => errors.push(e),
+        }
     }
+    leniency.check(errors)?;
+
+    Ok(Blocks {
+        header,
+        blocks: parsed,
+    })
+}
+
+/// Run a function, returning its result along with the wall time it took.
+pub fn timed<T>(f: impl FnOnce() -> T) -> (T, Duration) {
+    let start = Instant::now();
+    let result = f();
+    (result, start.elapsed())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{iter_lines, split_blocks, ParseLinesError};
 
-    Ok(vals)
+    #[test]
+    fn test_split_blocks() {
+        let blocks = split_blocks("\r\nhead\r\n\r\n\r\na\r\nb\r\n  \r\nc\n\n\n");
+        let lines: Vec<(usize, Vec<&str>)> =
+            blocks.into_iter().map(|b| (b.lineno, b.lines)).collect();
+
+        assert_eq!(
+            lines,
+            vec![(2, vec!["head"]), (5, vec!["a", "b"]), (8, vec!["c"]),]
+        );
+        assert!(split_blocks("\n \n").is_empty());
+    }
+
+    #[test]
+    fn test_iter_lines_normalizes() {
+        let input = &b"1\r\n2  \r\n\r\n3\n\n\n"[..];
+        let vals: Vec<Result<u32, _>> = iter_lines(input).collect();
+        assert_eq!(vals.len(), 4);
+        assert_eq!(vals[0].as_ref().unwrap(), &1);
+        assert_eq!(vals[1].as_ref().unwrap(), &2);
+        // An inner blank line is still parsed.
+        assert!(vals[2].is_err());
+        assert_eq!(vals[3].as_ref().unwrap(), &3);
+
+        let errors: Vec<usize> = iter_lines::<u32, _>(input)
+            .strict(true)
+            .filter_map(|r| match r {
+                Err(ParseLinesError::Format(d)) => Some(d.lineno),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(errors, vec![1, 2, 3, 5]);
+    }
 }
@@ -0,0 +1,256 @@
+//! Parser combinators for declaring line formats, e.g.
+//!
+//! ```text
+//! let point = separated_pair(integer(), literal(","), integer());
+//! let line = separated_pair(point, literal(" -> "), point);
+//! ```
+//!
+//! Parsers consume a prefix of their input, so any failure can be located in
+//! the line being parsed.
+
+use std::ops::Range;
+use std::str::FromStr;
+
+use thiserror::Error;
+
+use crate::diagnostic::Spanned;
+
+/// A parsed value and the input left after it.
+pub type PResult<'a, T> = Result<(T, &'a str), Failure>;
+
+/// Why a parser failed, and where: the failing token is `len` bytes long and
+/// starts `remaining` bytes before the end of the input.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Failure {
+    expected: String,
+    remaining: usize,
+    len: usize,
+}
+
+impl Failure {
+    fn at(input: &str, expected: impl Into<String>) -> Failure {
+        Failure {
+            expected: expected.into(),
+            remaining: input.len(),
+            len: input.chars().next().map_or(0, char::len_utf8),
+        }
+    }
+}
+
+/// A parse failure located within the line being parsed.
+#[derive(Error, Debug, Clone, PartialEq)]
+#[error("Expected {expected}")]
+pub struct SyntaxError {
+    pub expected: String,
+    pub span: Range<usize>,
+}
+
+impl Spanned for SyntaxError {
+    fn span(&self) -> Option<Range<usize>> {
+        Some(self.span.clone())
+    }
+}
+
+pub trait Parser<T> {
+    fn parse<'a>(&self, input: &'a str) -> PResult<'a, T>;
+}
+
+impl<T, F> Parser<T> for F
+where
+    F: for<'a> Fn(&'a str) -> PResult<'a, T>,
+{
+    fn parse<'a>(&self, input: &'a str) -> PResult<'a, T> {
+        self(input)
+    }
+}
+
+/// Pin a closure to the signature of a parser, which closures don't infer
+/// on their own.
+fn parser<T, F>(f: F) -> F
+where
+    F: for<'a> Fn(&'a str) -> PResult<'a, T>,
+{
+    f
+}
+
+/// Parse the whole of `s`, failing if any input is left over.
+pub fn parse_all<T>(p: &impl Parser<T>, s: &str) -> Result<T, SyntaxError> {
+    let located = |failure: Failure| {
+        let start = s.len() - failure.remaining;
+        SyntaxError {
+            expected: failure.expected,
+            span: start..start + failure.len,
+        }
+    };
+
+    match p.parse(s) {
+        Ok((value, "")) => Ok(value),
+        Ok((_, rest)) => Err(located(Failure {
+            expected: "end of line".to_owned(),
+            remaining: rest.len(),
+            len: rest.len(),
+        })),
+        Err(failure) => Err(located(failure)),
+    }
+}
+
+/// Match exactly `lit`.
+pub fn literal(lit: &'static str) -> impl Parser<()> {
+    parser(move |input| match input.strip_prefix(lit) {
+        Some(rest) => Ok(((), rest)),
+        None => Err(Failure::at(input, format!("{:?}", lit))),
+    })
+}
+
+/// One or more whitespace characters.
+#[allow(dead_code)]
+pub fn whitespace() -> impl Parser<()> {
+    parser(|input| {
+        let rest = input.trim_start();
+        match rest.len() < input.len() {
+            true => Ok(((), rest)),
+            false => Err(Failure::at(input, "whitespace")),
+        }
+    })
+}
+
+/// A decimal integer with an optional sign.
+pub fn integer<T: FromStr>() -> impl Parser<T> {
+    parser(|input| {
+        let sign = input.starts_with(['-', '+']) as usize;
+        let digits = input[sign..]
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(input.len() - sign);
+        if digits == 0 {
+            return Err(Failure::at(input, "an integer"));
+        }
+
+        let (token, rest) = input.split_at(sign + digits);
+        match token.parse() {
+            Ok(value) => Ok((value, rest)),
+            Err(_) => Err(Failure {
+                expected: format!("an integer that fits in {}", std::any::type_name::<T>()),
+                remaining: input.len(),
+                len: token.len(),
+            }),
+        }
+    })
+}
+
+/// `a` followed by `b`.
+#[allow(dead_code)]
+pub fn pair<A, B>(a: impl Parser<A>, b: impl Parser<B>) -> impl Parser<(A, B)> {
+    parser(move |input| {
+        let (x, rest) = a.parse(input)?;
+        let (y, rest) = b.parse(rest)?;
+        Ok(((x, y), rest))
+    })
+}
+
+/// `a` and `b` with `sep` between them, keeping only `a` and `b`.
+#[allow(dead_code)]
+pub fn separated_pair<A, B, S>(
+    a: impl Parser<A>,
+    sep: impl Parser<S>,
+    b: impl Parser<B>,
+) -> impl Parser<(A, B)> {
+    parser(move |input| {
+        let (x, rest) = a.parse(input)?;
+        let (_, rest) = sep.parse(rest)?;
+        let (y, rest) = b.parse(rest)?;
+        Ok(((x, y), rest))
+    })
+}
+
+/// One or more `item`s separated by `sep`.
+pub fn separated<T, S>(item: impl Parser<T>, sep: impl Parser<S>) -> impl Parser<Vec<T>> {
+    parser(move |input| {
+        let (first, mut rest) = item.parse(input)?;
+        let mut items = vec![first];
+        while let Ok((_, after_sep)) = sep.parse(rest) {
+            let (next, after_item) = item.parse(after_sep)?;
+            items.push(next);
+            rest = after_item;
+        }
+        Ok((items, rest))
+    })
+}
+
+/// `a`, or if it fails, `b`. If both fail, the failure is whichever got
+/// further, or both expectations if they failed at the same place.
+#[allow(dead_code)]
+pub fn alt<T>(a: impl Parser<T>, b: impl Parser<T>) -> impl Parser<T> {
+    parser(move |input| {
+        let a_failure = match a.parse(input) {
+            Ok(parsed) => return Ok(parsed),
+            Err(failure) => failure,
+        };
+        let b_failure = match b.parse(input) {
+            Ok(parsed) => return Ok(parsed),
+            Err(failure) => failure,
+        };
+
+        Err(match a_failure.remaining.cmp(&b_failure.remaining) {
+            std::cmp::Ordering::Less => a_failure,
+            std::cmp::Ordering::Greater => b_failure,
+            std::cmp::Ordering::Equal => Failure {
+                expected: format!("{} or {}", a_failure.expected, b_failure.expected),
+                ..a_failure
+            },
+        })
+    })
+}
+
+/// Transform the output of `p`.
+pub fn map<T, U>(p: impl Parser<T>, f: impl Fn(T) -> U) -> impl Parser<U> {
+    parser(move |input| {
+        let (value, rest) = p.parse(input)?;
+        Ok((f(value), rest))
+    })
+}
+
+/// Replace the output of `p` with `value`, e.g. for keywords.
+#[allow(dead_code)]
+pub fn value<T: Clone, U>(p: impl Parser<U>, value: T) -> impl Parser<T> {
+    map(p, move |_| value.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        alt, integer, literal, parse_all, separated, separated_pair, value, whitespace, SyntaxError,
+    };
+
+    #[test]
+    fn test_combinators() {
+        let point = || separated_pair(integer::<i16>(), literal(","), integer::<i16>());
+        let line = separated_pair(point(), literal(" -> "), point());
+        assert_eq!(parse_all(&line, "0,9 -> -5,9"), Ok(((0, 9), (-5, 9))));
+        assert_eq!(
+            parse_all(&line, "0,9 -> 5;9"),
+            Err(SyntaxError {
+                expected: "\",\"".to_owned(),
+                span: 8..9
+            })
+        );
+
+        let list = separated(integer::<u8>(), literal(","));
+        assert_eq!(parse_all(&list, "7,4,9"), Ok(vec![7, 4, 9]));
+        assert_eq!(
+            parse_all(&list, "7,400"),
+            Err(SyntaxError {
+                expected: "an integer that fits in u8".to_owned(),
+                span: 2..5
+            })
+        );
+        assert_eq!(parse_all(&list, "7,4 ").unwrap_err().span, 3..4);
+
+        let verb = alt(value(literal("up"), -1), value(literal("down"), 1));
+        let command = separated_pair(verb, whitespace(), integer::<i32>());
+        assert_eq!(parse_all(&command, "down  3"), Ok((1, 3)));
+        assert_eq!(
+            parse_all(&command, "left 3").unwrap_err().expected,
+            "\"up\" or \"down\""
+        );
+    }
+}
@@ -0,0 +1,389 @@
+//! A dense two-dimensional grid, for puzzles whose input is a map or board.
+
+use std::fmt;
+use std::ops::{Index, IndexMut, Range};
+use std::str::FromStr;
+
+use thiserror::Error;
+
+use crate::diagnostic::{span_of, Spanned};
+
+/// A position in a grid, as (row, column).
+pub type Pos = (usize, usize);
+
+#[allow(dead_code)]
+const ORTHOGONAL: [(isize, isize); 4] = [(-1, 0), (0, -1), (0, 1), (1, 0)];
+#[allow(dead_code)]
+const ALL_DIRECTIONS: [(isize, isize); 8] = [
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+    (0, -1),
+    (0, 1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+];
+
+/// A rectangular grid of cells, stored row by row.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+#[derive(Error, Debug)]
+pub enum GridParseError<E>
+where
+    E: std::error::Error + 'static,
+{
+    #[error("Empty grid")]
+    Empty,
+
+    #[error("Expected row {row} to contain {expected} cells, got {actual}")]
+    Ragged {
+        row: usize,
+        expected: usize,
+        actual: usize,
+    },
+
+    #[error("Failed to parse cell in row {row}, column {col}")]
+    Cell {
+        row: usize,
+        col: usize,
+        /// Span of the cell within its row.
+        span: Range<usize>,
+        source: E,
+    },
+}
+
+impl<E: std::error::Error + 'static> Spanned for GridParseError<E> {
+    fn span(&self) -> Option<Range<usize>> {
+        match self {
+            GridParseError::Cell { span, .. } => Some(span.clone()),
+            _ => None,
+        }
+    }
+
+    fn line(&self) -> usize {
+        match self {
+            GridParseError::Empty => 0,
+            GridParseError::Ragged { row, .. } => *row,
+            GridParseError::Cell { row, .. } => *row,
+        }
+    }
+}
+
+impl<T> Grid<T> {
+    /// A grid with every cell set to `fill`.
+    #[allow(dead_code)]
+    pub fn new(width: usize, height: usize, fill: T) -> Grid<T>
+    where
+        T: Clone,
+    {
+        Grid {
+            width,
+            height,
+            cells: vec![fill; width * height],
+        }
+    }
+
+    /// Build a grid from rows, which must all be the same length.
+    pub fn from_rows<E>(rows: Vec<Vec<T>>) -> Result<Grid<T>, GridParseError<E>>
+    where
+        E: std::error::Error + 'static,
+    {
+        let width = rows.first().ok_or(GridParseError::Empty)?.len();
+        if width == 0 {
+            return Err(GridParseError::Empty);
+        }
+        let height = rows.len();
+        let mut cells = Vec::with_capacity(width * height);
+
+        for (row, cells_in_row) in rows.into_iter().enumerate() {
+            if cells_in_row.len() != width {
+                return Err(GridParseError::Ragged {
+                    row,
+                    expected: width,
+                    actual: cells_in_row.len(),
+                });
+            }
+            cells.extend(cells_in_row);
+        }
+
+        Ok(Grid {
+            width,
+            height,
+            cells,
+        })
+    }
+
+    /// Parse a grid with one cell per character, e.g. a map of digits.
+    #[allow(dead_code)]
+    pub fn parse_chars(s: &str) -> Result<Grid<T>, GridParseError<T::Err>>
+    where
+        T: FromStr,
+        T::Err: std::error::Error + 'static,
+    {
+        Self::parse_with(s, |line| {
+            line.char_indices()
+                .map(|(i, c)| &line[i..i + c.len_utf8()])
+                .collect()
+        })
+    }
+
+    /// Parse a grid whose cells are separated by whitespace, e.g. a bingo
+    /// board.
+    pub fn parse_whitespace(s: &str) -> Result<Grid<T>, GridParseError<T::Err>>
+    where
+        T: FromStr,
+        T::Err: std::error::Error + 'static,
+    {
+        Self::parse_with(s, |line| line.split_whitespace().collect())
+    }
+
+    fn parse_with<'a>(
+        s: &'a str,
+        split: impl Fn(&'a str) -> Vec<&'a str>,
+    ) -> Result<Grid<T>, GridParseError<T::Err>>
+    where
+        T: FromStr,
+        T::Err: std::error::Error + 'static,
+    {
+        let rows = s
+            .lines()
+            .enumerate()
+            .map(|(row, line)| {
+                split(line)
+                    .into_iter()
+                    .enumerate()
+                    .map(|(col, cell)| {
+                        cell.parse().map_err(|source| GridParseError::Cell {
+                            row,
+                            col,
+                            span: span_of(line, cell),
+                            source,
+                        })
+                    })
+                    .collect::<Result<Vec<T>, _>>()
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Self::from_rows(rows)
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn contains(&self, (row, col): Pos) -> bool {
+        row < self.height && col < self.width
+    }
+
+    pub fn get(&self, pos: Pos) -> Option<&T> {
+        match self.contains(pos) {
+            true => Some(&self.cells[pos.0 * self.width + pos.1]),
+            false => None,
+        }
+    }
+
+    pub fn get_mut(&mut self, pos: Pos) -> Option<&mut T> {
+        match self.contains(pos) {
+            true => Some(&mut self.cells[pos.0 * self.width + pos.1]),
+            false => None,
+        }
+    }
+
+    /// Apply `f` to every cell.
+    pub fn map<U>(&self, f: impl FnMut(&T) -> U) -> Grid<U> {
+        Grid {
+            width: self.width,
+            height: self.height,
+            cells: self.cells.iter().map(f).collect(),
+        }
+    }
+
+    /// Every cell, row by row.
+    pub fn cells(&self) -> impl Iterator<Item = &T> {
+        self.cells.iter()
+    }
+
+    /// Every position and its cell, row by row.
+    pub fn iter(&self) -> impl Iterator<Item = (Pos, &T)> {
+        let width = self.width;
+        self.cells
+            .iter()
+            .enumerate()
+            .map(move |(i, cell)| ((i / width, i % width), cell))
+    }
+
+    /// Position of the first cell, row by row, matching `pred`.
+    pub fn position(&self, pred: impl Fn(&T) -> bool) -> Option<Pos> {
+        self.iter().find(|(_, cell)| pred(cell)).map(|(pos, _)| pos)
+    }
+
+    pub fn row(&self, row: usize) -> &[T] {
+        if row >= self.height {
+            self.out_of_bounds(format_args!("row {}", row));
+        }
+        &self.cells[row * self.width..(row + 1) * self.width]
+    }
+
+    #[allow(dead_code)]
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        (0..self.height).map(move |row| self.row(row))
+    }
+
+    pub fn column(&self, col: usize) -> impl Iterator<Item = &T> {
+        if col >= self.width {
+            self.out_of_bounds(format_args!("column {}", col));
+        }
+        self.cells.iter().skip(col).step_by(self.width)
+    }
+
+    #[allow(dead_code)]
+    pub fn columns(&self) -> impl Iterator<Item = impl Iterator<Item = &T>> {
+        (0..self.width).map(move |col| self.column(col))
+    }
+
+    /// Positions above, left, right and below `pos` that are in the grid.
+    #[allow(dead_code)]
+    pub fn neighbors4(&self, pos: Pos) -> impl Iterator<Item = Pos> + '_ {
+        self.offsets(pos, &ORTHOGONAL)
+    }
+
+    /// Positions surrounding `pos`, including diagonals, that are in the grid.
+    #[allow(dead_code)]
+    pub fn neighbors8(&self, pos: Pos) -> impl Iterator<Item = Pos> + '_ {
+        self.offsets(pos, &ALL_DIRECTIONS)
+    }
+
+    fn out_of_bounds(&self, what: fmt::Arguments) -> ! {
+        panic!(
+            "{} out of bounds for {}x{} grid",
+            what, self.width, self.height
+        )
+    }
+
+    #[allow(dead_code)]
+    fn offsets(
+        &self,
+        (row, col): Pos,
+        deltas: &'static [(isize, isize)],
+    ) -> impl Iterator<Item = Pos> + '_ {
+        deltas.iter().filter_map(move |&(dr, dc)| {
+            let pos = (row.checked_add_signed(dr)?, col.checked_add_signed(dc)?);
+            self.contains(pos).then_some(pos)
+        })
+    }
+}
+
+impl<T> Index<Pos> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, pos: Pos) -> &T {
+        match self.get(pos) {
+            Some(cell) => cell,
+            None => self.out_of_bounds(format_args!("position {:?}", pos)),
+        }
+    }
+}
+
+impl<T> IndexMut<Pos> for Grid<T> {
+    fn index_mut(&mut self, pos: Pos) -> &mut T {
+        if !self.contains(pos) {
+            self.out_of_bounds(format_args!("position {:?}", pos));
+        }
+        self.get_mut(pos).expect("position is in the grid")
+    }
+}
+
+/// Renders one row per line. Cells are right-aligned to the widest cell and,
+/// unless every cell is a single character, separated by spaces.
+impl<T: fmt::Display> fmt::Display for Grid<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let rendered: Vec<String> = self.cells.iter().map(|c| c.to_string()).collect();
+        let width = rendered
+            .iter()
+            .map(|c| c.chars().count())
+            .max()
+            .unwrap_or(0);
+        let separator = if width > 1 { " " } else { "" };
+
+        for (i, row) in rendered.chunks(self.width.max(1)).enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            let cells: Vec<String> = row.iter().map(|c| format!("{:>1$}", c, width)).collect();
+            write!(f, "{}", cells.join(separator))?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Grid, GridParseError};
+    use crate::diagnostic::Spanned;
+
+    #[test]
+    fn test_parse_and_render() {
+        let grid: Grid<u8> = Grid::parse_chars("123\n456\n").unwrap();
+        assert_eq!((grid.width(), grid.height()), (3, 2));
+        assert_eq!(grid[(1, 2)], 6);
+        assert_eq!(grid.get((2, 0)), None);
+        assert_eq!(grid.row(1), &[4, 5, 6]);
+        assert_eq!(grid.column(1).copied().collect::<Vec<_>>(), vec![2, 5]);
+        assert_eq!(grid.to_string(), "123\n456");
+
+        let grid: Grid<u8> = Grid::parse_whitespace(" 8  2\n21  9").unwrap();
+        assert_eq!(grid.to_string(), " 8  2\n21  9");
+
+        let err = Grid::<u8>::parse_whitespace("1 2\n3 x").unwrap_err();
+        assert!(matches!(err, GridParseError::Cell { row: 1, col: 1, .. }));
+        assert_eq!((err.line(), err.span()), (1, Some(2..3)));
+        assert!(matches!(
+            Grid::<u8>::parse_chars("12\n3"),
+            Err(GridParseError::Ragged { row: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn test_empty() {
+        assert!(matches!(
+            Grid::<u8>::parse_chars("\n"),
+            Err(GridParseError::Empty)
+        ));
+        let grid = Grid::new(0, 2, 0);
+        assert_eq!(grid.rows().collect::<Vec<_>>(), vec![&[] as &[i32]; 2]);
+        assert_eq!(grid.columns().count(), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "row 2 out of bounds for 3x2 grid")]
+    fn test_row_out_of_bounds() {
+        Grid::new(3, 2, 0).row(2);
+    }
+
+    #[test]
+    #[should_panic(expected = "column 4 out of bounds for 3x2 grid")]
+    fn test_column_out_of_bounds() {
+        let _ = Grid::new(3, 2, 0).column(4);
+    }
+
+    #[test]
+    fn test_neighbors() {
+        let grid = Grid::new(3, 3, 0);
+        let mut corner: Vec<_> = grid.neighbors4((0, 0)).collect();
+        corner.sort();
+        assert_eq!(corner, vec![(0, 1), (1, 0)]);
+        assert_eq!(grid.neighbors8((0, 2)).count(), 3);
+        assert_eq!(grid.neighbors8((1, 1)).count(), 8);
+    }
+}
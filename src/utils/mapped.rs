@@ -0,0 +1,257 @@
+//! Zero-copy loading for very large inputs: files are memory-mapped and lines
+//! are parsed straight from the mapped bytes, without allocating a `String`
+//! per line. Integers have a byte-level fast path that skips UTF-8
+//! validation altogether.
+
+use std::fs::File;
+use std::io::{Error as IOError, ErrorKind, Read};
+use std::ops::Deref;
+use std::str::FromStr;
+
+use memmap2::Mmap;
+
+use crate::diagnostic::{message_chain, Diagnostic, Leniency, Spanned};
+use crate::input::{normalize_line, InputError, InputSource, Issue, IssueKind};
+use crate::utils::ParseLinesError;
+
+/// The bytes of an input: mapped for files, read into memory for anything
+/// that can't be mapped.
+pub enum MappedInput {
+    Mapped(Mmap),
+    Owned(Vec<u8>),
+}
+
+impl MappedInput {
+    pub fn open(source: &InputSource) -> Result<MappedInput, InputError> {
+        let read_error = |e: IOError| InputError::Read {
+            name: source.to_string(),
+            source: e,
+        };
+
+        match source {
+            InputSource::Path(path) => {
+                let mut file = File::open(path).map_err(|source| InputError::FileOpen {
+                    path: path.clone(),
+                    source,
+                })?;
+                // SAFETY: the map is only read, and puzzle inputs aren't
+                // modified while they're being solved.
+                match unsafe { Mmap::map(&file) } {
+                    Ok(map) => Ok(MappedInput::Mapped(map)),
+                    // Pipes and other special files can't be mapped.
+                    Err(_) => {
+                        let mut bytes = Vec::new();
+                        file.read_to_end(&mut bytes).map_err(read_error)?;
+                        Ok(MappedInput::Owned(bytes))
+                    }
+                }
+            }
+            InputSource::Stdin => {
+                let mut bytes = Vec::new();
+                std::io::stdin()
+                    .read_to_end(&mut bytes)
+                    .map_err(read_error)?;
+                Ok(MappedInput::Owned(bytes))
+            }
+            InputSource::Text(text) => Ok(MappedInput::Owned(text.clone().into_bytes())),
+        }
+    }
+}
+
+impl Deref for MappedInput {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            MappedInput::Mapped(map) => map,
+            MappedInput::Owned(bytes) => bytes,
+        }
+    }
+}
+
+/// Values that can be parsed directly from the bytes of a line. `from_bytes`
+/// only has to handle well-formed input: anything it rejects is re-parsed
+/// with `FromStr` to report the error.
+pub trait FromBytes: FromStr {
+    fn from_bytes(bytes: &[u8]) -> Option<Self>;
+}
+
+macro_rules! impl_from_bytes {
+    ($($t:ty),*) => {$(
+        impl FromBytes for $t {
+            fn from_bytes(bytes: &[u8]) -> Option<$t> {
+                let (negative, digits) = match bytes {
+                    [b'-', rest @ ..] if <$t>::MIN != 0 => (true, rest),
+                    [b'+', rest @ ..] => (false, rest),
+                    _ => (false, bytes),
+                };
+                if digits.is_empty() {
+                    return None;
+                }
+
+                digits.iter().try_fold(0 as $t, |acc, &b| {
+                    let digit = b.wrapping_sub(b'0');
+                    if digit > 9 {
+                        return None;
+                    }
+                    let acc = acc.checked_mul(10)?;
+                    match negative {
+                        true => acc.checked_sub(digit as $t),
+                        false => acc.checked_add(digit as $t),
+                    }
+                })
+            }
+        }
+    )*};
+}
+
+impl_from_bytes!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+
+/// Parse each line of a mapped input, normalizing and reporting errors as
+/// `parse_lines_from_source` does.
+pub fn parse_mapped_lines<T>(
+    bytes: &[u8],
+    name: &str,
+    leniency: &mut Leniency,
+) -> Result<Vec<T>, ParseLinesError<T>>
+where
+    T: FromStr,
+    <T as FromStr>::Err: std::error::Error + Spanned + Send + Sync + 'static,
+{
+    parse_mapped_with(bytes, name, leniency, |_| None)
+}
+
+/// Like `parse_mapped_lines`, but parsing well-formed integers straight from
+/// their bytes.
+pub fn parse_mapped_ints<T>(
+    bytes: &[u8],
+    name: &str,
+    leniency: &mut Leniency,
+) -> Result<Vec<T>, ParseLinesError<T>>
+where
+    T: FromBytes,
+    <T as FromStr>::Err: std::error::Error + Spanned + Send + Sync + 'static,
+{
+    parse_mapped_with(bytes, name, leniency, T::from_bytes)
+}
+
+/// Parse each line with `fast`, falling back to normalizing the line and
+/// parsing it with `FromStr` when that fails.
+fn parse_mapped_with<T>(
+    bytes: &[u8],
+    name: &str,
+    leniency: &mut Leniency,
+    fast: impl Fn(&[u8]) -> Option<T>,
+) -> Result<Vec<T>, ParseLinesError<T>>
+where
+    T: FromStr,
+    <T as FromStr>::Err: std::error::Error + Spanned + Send + Sync + 'static,
+{
+    // Lines up to the end of the last one with any content; what follows is
+    // blank lines, which are dropped.
+    let content_len = bytes.len()
+        - bytes
+            .iter()
+            .rev()
+            .take_while(|b| b.is_ascii_whitespace())
+            .count();
+    let end = bytes[content_len..]
+        .iter()
+        .position(|&b| b == b'\n')
+        .map_or(bytes.len(), |i| content_len + i);
+    let lines = match end {
+        0 => None,
+        _ => Some(bytes[..end].split(|&b| b == b'\n')),
+    };
+
+    let mut values = Vec::new();
+    let mut errors = Vec::new();
+    let mut count = 0;
+    for (index, raw) in lines.into_iter().flatten().enumerate() {
+        let lineno = index + 1;
+        count = lineno;
+        if let Some(value) = fast(raw) {
+            values.push(value);
+            continue;
+        }
+
+        let line = std::str::from_utf8(raw).map_err(|e| ParseLinesError::LineRead {
+            source: IOError::new(ErrorKind::InvalidData, e),
+            name: name.to_owned(),
+            lineno,
+        })?;
+        let (normalized, issue) = normalize_line(line);
+        if let (true, Some(issue)) = (leniency.is_strict(), issue) {
+            errors.push(issue.diagnostic(&name, lineno, line));
+            continue;
+        }
+
+        match T::from_str(normalized) {
+            Ok(value) => values.push(value),
+            Err(error) => errors.push(Diagnostic {
+                name: name.to_owned(),
+                lineno,
+                line: normalized.to_owned(),
+                span: error.span(),
+                message: message_chain(&error),
+            }),
+        }
+    }
+
+    let trailing = match end {
+        0 => !bytes.is_empty(),
+        _ => bytes.len() > end + 1,
+    };
+    if trailing && leniency.is_strict() {
+        let issue = Issue {
+            kind: IssueKind::TrailingBlankLines,
+            span: 0..0,
+        };
+        errors.push(issue.diagnostic(&name, count + 1, ""));
+    }
+
+    leniency.check(errors)?;
+    Ok(values)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_mapped_ints, parse_mapped_lines, FromBytes};
+    use crate::diagnostic::Leniency;
+    use crate::utils::ParseLinesError;
+
+    #[test]
+    fn test_from_bytes() {
+        assert_eq!(u32::from_bytes(b"199"), Some(199));
+        assert_eq!(i8::from_bytes(b"-128"), Some(-128));
+        assert_eq!(i8::from_bytes(b"+12"), Some(12));
+        assert_eq!(u8::from_bytes(b"256"), None);
+        assert_eq!(u8::from_bytes(b"-0"), None);
+        assert_eq!(u32::from_bytes(b""), None);
+        assert_eq!(u32::from_bytes(b"12\r"), None);
+    }
+
+    #[test]
+    fn test_parse_mapped() {
+        let input = b"1\r\n2  \r\n\r\n3\n\n\n";
+
+        let mut leniency = Leniency::new(true);
+        let vals: Vec<u32> = parse_mapped_ints(input, "input.txt", &mut leniency).unwrap();
+        assert_eq!(vals, vec![1, 2, 3]);
+        // The inner blank line is still parsed.
+        assert_eq!(leniency.into_warnings()[0].lineno, 3);
+
+        let mut strict = Leniency::default().with_strict(true);
+        match parse_mapped_lines::<u32>(input, "input.txt", &mut strict) {
+            Err(ParseLinesError::Invalid(diagnostics)) => {
+                let linenos: Vec<usize> = diagnostics.0.iter().map(|d| d.lineno).collect();
+                assert_eq!(linenos, vec![1, 2, 3, 5]);
+            }
+            other => panic!("expected diagnostics, got {:?}", other),
+        }
+
+        let vals: Vec<u32> = parse_mapped_ints(b"", "input.txt", &mut strict).unwrap();
+        assert!(vals.is_empty());
+        assert!(parse_mapped_ints::<u32>(b"\n", "input.txt", &mut strict).is_err());
+    }
+}
@@ -0,0 +1,132 @@
+//! Runtime support for `#[derive(PuzzleParse)]` from the `aoc-derive` crate,
+//! which generates parsers that call these functions one pattern segment at
+//! a time.
+
+use crate::diagnostic::span_of;
+use crate::utils::combinator::SyntaxError;
+
+/// A failure to find `expected` at the start of `rest`, located in `line`.
+fn error_at(line: &str, rest: &str, expected: String) -> SyntaxError {
+    let start = span_of(line, rest).start;
+    SyntaxError {
+        expected,
+        span: start..start + rest.chars().next().map_or(0, char::len_utf8),
+    }
+}
+
+/// Match the literal text `literal` at the start of `input`, returning the
+/// input after it. Each space in `literal` matches one or more whitespace
+/// characters.
+pub fn expect<'a>(line: &str, input: &'a str, literal: &str) -> Result<&'a str, SyntaxError> {
+    let mut rest = input;
+    for (i, word) in literal.split(' ').enumerate() {
+        if i > 0 {
+            let trimmed = rest.trim_start();
+            if trimmed.len() == rest.len() {
+                return Err(error_at(line, rest, format!("{:?}", literal)));
+            }
+            rest = trimmed;
+        }
+        rest = rest
+            .strip_prefix(word)
+            .ok_or_else(|| error_at(line, rest, format!("{:?}", literal)))?;
+    }
+    Ok(rest)
+}
+
+/// Split the token for a field off the start of `input`: everything up to
+/// the literal text `next` that follows the field in its pattern, or the rest
+/// of the line if it's the last segment.
+pub fn field<'a>(input: &'a str, next: Option<&str>) -> (&'a str, &'a str) {
+    let end = match next {
+        None => None,
+        Some(next) if next.starts_with(' ') => input.find(char::is_whitespace),
+        Some(next) => input.find(next.split(' ').next().unwrap_or(next)),
+    }
+    .unwrap_or(input.len());
+    input.split_at(end)
+}
+
+/// Check that nothing is left after the last segment of a pattern.
+pub fn end(line: &str, rest: &str) -> Result<(), SyntaxError> {
+    match rest {
+        "" => Ok(()),
+        _ => Err(SyntaxError {
+            expected: "end of line".to_owned(),
+            span: span_of(line, rest),
+        }),
+    }
+}
+
+/// The error for a line that starts with none of an enum's keywords,
+/// pointing at its first word.
+pub fn keyword_error(line: &str, keywords: &[&str]) -> SyntaxError {
+    let expected: Vec<String> = keywords.iter().map(|k| format!("{:?}", k)).collect();
+    let word = line.split(char::is_whitespace).next().unwrap_or(line);
+    SyntaxError {
+        expected: expected.join(" or "),
+        span: span_of(line, word),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use aoc_derive::PuzzleParse;
+
+    use crate::diagnostic::Spanned;
+    use crate::utils::combinator::SyntaxError;
+
+    #[derive(PuzzleParse, Debug, PartialEq)]
+    #[pattern("{name}: {low}-{high}")]
+    struct Range {
+        name: String,
+        low: u8,
+        high: u8,
+    }
+
+    #[derive(PuzzleParse, Debug, PartialEq)]
+    enum Op {
+        #[pattern("noop")]
+        Noop,
+        #[pattern("addx {0}")]
+        Add(i32),
+        #[pattern("move {0} to {1}")]
+        Move(u8, char),
+    }
+
+    #[test]
+    fn test_derive() {
+        let range = "depth: 3-40".parse::<Range>().unwrap();
+        let expected = Range {
+            name: "depth".to_owned(),
+            low: 3,
+            high: 40,
+        };
+        assert_eq!(range, expected);
+
+        let e = "depth 3-40".parse::<Range>().unwrap_err();
+        assert_eq!(e.to_string(), "Expected \": \"");
+        assert_eq!(e.span(), Some(10..10));
+
+        let e = "depth: 3-400".parse::<Range>().unwrap_err();
+        assert!(matches!(e, RangeParseError::High { .. }));
+        assert_eq!(e.to_string(), "Invalid {high} in \"{name}: {low}-{high}\"");
+        assert_eq!(e.span(), Some(9..12));
+
+        assert_eq!("noop".parse::<Op>().unwrap(), Op::Noop);
+        assert_eq!("addx  -3".parse::<Op>().unwrap(), Op::Add(-3));
+        assert_eq!("move 2 to x".parse::<Op>().unwrap(), Op::Move(2, 'x'));
+        assert!(matches!(
+            "move 2 to xy".parse::<Op>(),
+            Err(OpParseError::Move1 { .. })
+        ));
+        assert!(matches!(
+            "noop 1".parse::<Op>(),
+            Err(OpParseError::Syntax(SyntaxError { span, .. })) if span == (4..6)
+        ));
+
+        let e = "jump 3".parse::<Op>().unwrap_err();
+        assert_eq!(e.to_string(), "Expected \"noop\" or \"addx\" or \"move\"");
+        assert_eq!(e.span(), Some(0..4));
+    }
+}
@@ -0,0 +1,326 @@
+use serde::{Deserialize, Deserializer, Serialize};
+use structopt::StructOpt;
+use thiserror::Error;
+
+use crate::input::InputSource;
+use crate::report::{self, csv_opt, Format, Record, Solution};
+use crate::solver::{Day, Parts};
+use crate::utils::ProblemResult;
+
+#[derive(StructOpt, Debug)]
+pub struct Verify {
+    /// File of known-correct answers.
+    #[structopt(long = "answers", default_value = "answers.toml")]
+    answers: String,
+
+    /// Only verify this day.
+    #[structopt(long = "day")]
+    day: Option<u32>,
+}
+
+/// Contents of the answers file, e.g.
+///
+/// [[answers]]
+/// day = 1
+/// input = "inputs/problem1/input.txt"
+/// part1 = 1195
+/// part2 = 1235
+///
+/// `input` defaults to the day's default input. Either part may be omitted
+/// if its answer isn't known yet.
+#[derive(Deserialize, Debug)]
+struct AnswersFile {
+    #[serde(default)]
+    answers: Vec<Expected>,
+}
+
+#[derive(Deserialize, Debug)]
+struct Expected {
+    day: u32,
+    input: Option<String>,
+    #[serde(default, deserialize_with = "answer")]
+    part1: Option<String>,
+    #[serde(default, deserialize_with = "answer")]
+    part2: Option<String>,
+}
+
+/// Accept answers written either as strings or as integers.
+fn answer<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<String>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Answer {
+        Int(i64),
+        Str(String),
+    }
+
+    Ok(Some(match Answer::deserialize(deserializer)? {
+        Answer::Int(i) => i.to_string(),
+        Answer::Str(s) => s,
+    }))
+}
+
+#[derive(Error, Debug)]
+pub enum AnswersError {
+    #[error("Failed to read answers file: {path}")]
+    Read {
+        path: String,
+        source: std::io::Error,
+    },
+
+    #[error("Failed to parse answers file: {path}")]
+    Parse {
+        path: String,
+        source: toml::de::Error,
+    },
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum Status {
+    Pass,
+    Fail,
+    Missing,
+    Error,
+}
+
+impl Status {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Status::Pass => "pass",
+            Status::Fail => "fail",
+            Status::Missing => "missing",
+            Status::Error => "error",
+        }
+    }
+}
+
+/// The result of checking one part of one day against its recorded answer.
+#[derive(Serialize, Debug)]
+struct Check {
+    day: u32,
+    part: u8,
+    input: String,
+    status: Status,
+    expected: Option<String>,
+    actual: Option<String>,
+    error: Option<String>,
+}
+
+impl Record for Check {
+    const CSV_HEADER: &'static str = "day,part,input,status,expected,actual,error";
+
+    fn csv_fields(&self) -> Vec<String> {
+        vec![
+            self.day.to_string(),
+            self.part.to_string(),
+            self.input.clone(),
+            self.status.as_str().to_owned(),
+            csv_opt(&self.expected),
+            csv_opt(&self.actual),
+            csv_opt(&self.error),
+        ]
+    }
+}
+
+impl Verify {
+    pub fn run(&self, days: &[Day], parts: Parts, format: Format) -> ProblemResult {
+        let expected = self.load()?;
+
+        let mut checks = Vec::new();
+        for day in days.iter().filter(|d| self.day.is_none_or(|n| n == d.day)) {
+            let entries: Vec<&Expected> = expected.iter().filter(|e| e.day == day.day).collect();
+            if entries.is_empty() {
+                checks.extend(missing(day, parts));
+            }
+            for entry in entries {
+                checks.extend(check(day, entry, parts));
+            }
+        }
+
+        if format == Format::Text {
+            print_checks(&checks);
+        } else {
+            report::print_records(format, &checks)?;
+        }
+
+        let failures = checks
+            .iter()
+            .filter(|c| matches!(c.status, Status::Fail | Status::Error))
+            .count();
+        if failures > 0 {
+            return Err(anyhow::anyhow!(
+                "{} of {} checks failed",
+                failures,
+                checks.len()
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn load(&self) -> Result<Vec<Expected>, AnswersError> {
+        let text = std::fs::read_to_string(&self.answers).map_err(|source| AnswersError::Read {
+            path: self.answers.clone(),
+            source,
+        })?;
+        let file: AnswersFile = toml::from_str(&text).map_err(|source| AnswersError::Parse {
+            path: self.answers.clone(),
+            source,
+        })?;
+        Ok(file.answers)
+    }
+}
+
+const DEFAULT_INPUT: &str = "default";
+
+/// Checks for a day with no recorded answers.
+fn missing(day: &Day, parts: Parts) -> Vec<Check> {
+    parts
+        .iter()
+        .map(|part| Check {
+            day: day.day,
+            part,
+            input: DEFAULT_INPUT.to_owned(),
+            status: Status::Missing,
+            expected: None,
+            actual: None,
+            error: None,
+        })
+        .collect()
+}
+
+/// Rerun a day on the entry's input and compare the selected parts.
+fn check(day: &Day, entry: &Expected, parts: Parts) -> Vec<Check> {
+    let puzzle = match &entry.input {
+        Some(path) => day.with_source(&[], InputSource::Path(path.clone())),
+        None => Ok(day.with_defaults()),
+    };
+    let input = entry.input.as_deref().unwrap_or(DEFAULT_INPUT).to_owned();
+
+    let solution: ProblemResult<Solution> = puzzle.and_then(|puzzle| puzzle.solve(parts));
+
+    parts
+        .iter()
+        .map(|part| {
+            let expected = if part == 1 {
+                entry.part1.clone()
+            } else {
+                entry.part2.clone()
+            };
+            let actual = match &solution {
+                Ok(s) => s.part(part).expect("selected part was run").answer.as_ref(),
+                Err(e) => Err(e),
+            };
+
+            let (status, actual, error) = match (actual, &expected) {
+                (Err(e), _) => (Status::Error, None, Some(format!("{:#}", e))),
+                (Ok(a), None) => (Status::Missing, Some(a.clone()), None),
+                (Ok(a), Some(x)) if a == x => (Status::Pass, Some(a.clone()), None),
+                (Ok(a), Some(_)) => (Status::Fail, Some(a.clone()), None),
+            };
+
+            Check {
+                day: day.day,
+                part,
+                input: input.clone(),
+                status,
+                expected,
+                actual,
+                error,
+            }
+        })
+        .collect()
+}
+
+fn print_checks(checks: &[Check]) {
+    for c in checks {
+        let detail = match c.status {
+            Status::Pass | Status::Missing => String::new(),
+            Status::Fail => format!(
+                ": expected {}, got {}",
+                csv_opt(&c.expected),
+                csv_opt(&c.actual)
+            ),
+            Status::Error => format!(": {}", csv_opt(&c.error)),
+        };
+        println!(
+            "Day {} part {} ({}): {}{}",
+            c.day,
+            c.part,
+            c.input,
+            c.status.as_str(),
+            detail
+        );
+    }
+
+    let count = |status| checks.iter().filter(|c| c.status == status).count();
+    println!(
+        "{} passed, {} failed, {} missing, {} errors",
+        count(Status::Pass),
+        count(Status::Fail),
+        count(Status::Missing),
+        count(Status::Error)
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check, AnswersFile, Expected, Status};
+    use crate::problem1::P1;
+    use crate::solver::{Day, Parts};
+
+    #[test]
+    fn test_parse_answers() {
+        let file: AnswersFile = toml::from_str(
+            r#"
+[[answers]]
+day = 1
+part1 = 7
+part2 = "five"
+"#,
+        )
+        .unwrap();
+
+        let entry = &file.answers[0];
+        assert_eq!(entry.day, 1);
+        assert_eq!(entry.input, None);
+        assert_eq!(entry.part1.as_deref(), Some("7"));
+        assert_eq!(entry.part2.as_deref(), Some("five"));
+    }
+
+    #[test]
+    fn test_check() {
+        let path = std::env::temp_dir().join(format!("aoc-verify-{}.txt", std::process::id()));
+        std::fs::write(&path, "199\n200\n208\n210\n200\n207\n240\n269\n260\n263\n").unwrap();
+        let day = Day::new::<P1>();
+        let entry = |input: String, part2: Option<&str>| Expected {
+            day: 1,
+            input: Some(input),
+            part1: Some("7".to_owned()),
+            part2: part2.map(str::to_owned),
+        };
+        let statuses = |entry: &Expected| -> Vec<Status> {
+            check(&day, entry, Parts::Both)
+                .iter()
+                .map(|c| c.status)
+                .collect()
+        };
+        let input = path.to_string_lossy().into_owned();
+
+        assert_eq!(
+            statuses(&entry(input.clone(), Some("5"))),
+            vec![Status::Pass, Status::Pass]
+        );
+        let checks = check(&day, &entry(input.clone(), Some("6")), Parts::Both);
+        assert_eq!(checks[1].status, Status::Fail);
+        assert_eq!(checks[1].actual.as_deref(), Some("5"));
+        assert_eq!(
+            statuses(&entry(input, None)),
+            vec![Status::Pass, Status::Missing]
+        );
+        std::fs::remove_file(&path).unwrap();
+
+        let missing_file = entry(path.to_string_lossy().into_owned(), Some("5"));
+        assert_eq!(statuses(&missing_file), vec![Status::Error, Status::Error]);
+    }
+}